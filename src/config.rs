@@ -1,10 +1,20 @@
 use std::{
+    collections::BTreeMap,
     fs::File,
     io::Read,
     path::{Path, PathBuf},
 };
 
-use crate::{format::FormatRadix, options::Options};
+use crate::{
+    expression::eval::Env,
+    format::{
+        Case, FormatRadix, GroupSizes, Negative, OutputFormat, Pad, Prefix, Row, Separator,
+        SizePrefixes,
+    },
+    layout::Layout,
+    options::Options,
+    word::{Overflow, Width, Word},
+};
 use anyhow::Result as AResult;
 use colored::Colorize;
 use getset::Getters;
@@ -24,6 +34,42 @@ pub struct Config {
 
     #[serde(default)]
     punctuate_output: bool,
+
+    #[serde(default)]
+    separator: Separator,
+
+    #[serde(default)]
+    group_sizes: GroupSizes,
+
+    #[serde(default)]
+    width: Width,
+
+    #[serde(default = "default_signed")]
+    signed: bool,
+
+    #[serde(default)]
+    overflow: Overflow,
+
+    #[serde(default)]
+    negative: Negative,
+
+    #[serde(default)]
+    pad: Pad,
+
+    #[serde(default)]
+    case: Case,
+
+    #[serde(default)]
+    prefix: Prefix,
+
+    #[serde(default)]
+    size_prefixes: SizePrefixes,
+
+    #[serde(default)]
+    all_rows: Vec<Row>,
+
+    #[serde(default)]
+    layouts: BTreeMap<String, Layout>,
 }
 
 impl Config {
@@ -31,6 +77,27 @@ impl Config {
         if options.punctuate_output {
             self.punctuate_output = true;
         }
+        if let Some(separator) = options.sep {
+            self.separator = separator;
+        }
+        for group_size in &options.group {
+            self.group_sizes.set(group_size.radix, group_size.size);
+        }
+
+        if let Some(width) = options.width {
+            self.width = width;
+        }
+        if options.unsigned {
+            self.signed = false;
+        } else if options.signed {
+            self.signed = true;
+        }
+        if let Some(overflow) = options.overflow {
+            self.overflow = overflow;
+        }
+        if let Some(pad) = options.pad {
+            self.pad = pad;
+        }
 
         if options.hex {
             self.output_radix = FormatRadix::Hex;
@@ -42,6 +109,37 @@ impl Config {
             self.output_radix = FormatRadix::Binary;
         }
     }
+
+    pub fn word(&self) -> Word {
+        Word::new(self.width, self.signed)
+    }
+
+    /// Returns an environment with the word, overflow policy, size prefixes and layouts of
+    /// the config.
+    pub fn env(&self) -> Env {
+        let mut env = Env::new(self.word(), self.overflow);
+        env.set_size_prefixes(self.size_prefixes);
+        for (name, layout) in &self.layouts {
+            env.define_layout(name, layout.clone());
+        }
+        env
+    }
+
+    /// Returns the output format described by the config.
+    pub fn output_format(&self) -> OutputFormat {
+        OutputFormat::default()
+            .with_format_radix(self.output_radix)
+            .with_punctuate_number(self.punctuate_output)
+            .with_separator(self.separator)
+            .with_group_sizes(self.group_sizes)
+            .with_word(self.word())
+            .with_negative(self.negative)
+            .with_pad(self.pad)
+            .with_case(self.case)
+            .with_prefix(self.prefix)
+            .with_size_prefixes(self.size_prefixes)
+            .with_rows(self.all_rows.clone())
+    }
 }
 
 fn default_prompt() -> String {
@@ -52,6 +150,10 @@ fn default_header() -> bool {
     true
 }
 
+fn default_signed() -> bool {
+    true
+}
+
 fn config_locations() -> Vec<PathBuf> {
     match home::home_dir() {
         Some(home) => {
@@ -94,23 +196,71 @@ pub fn read_config<T: AsRef<Path>>(user_path: Option<T>) -> AResult<Config> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use clap::Parser;
 
     #[test]
     fn test_config_deserialize() {
         let config_str = "prompt: $
 header: false
 output_radix: Octal
-punctuate_output: true";
+punctuate_output: true
+separator: ','
+group_sizes:
+  binary: 8
+width: 32
+signed: false
+overflow: Checked
+negative: Twos
+pad: 16
+case: Upper
+prefix: Intel
+size_prefixes: SI
+all_rows: [Signed, Ruler]
+layouts:
+  CR0: PE=0, MP=1, PG=31
+  PTE: present=0, rw=1, pfn=12:51";
         let config: Config = serde_yaml::from_str(config_str).unwrap();
+        let mut group_sizes = GroupSizes::default();
+        group_sizes.set(FormatRadix::Binary, 8);
         let expected_config = Config {
             prompt: String::from("$"),
             header: false,
             output_radix: FormatRadix::Octal,
             punctuate_output: true,
+            separator: Separator::try_from(',').unwrap(),
+            group_sizes,
+            width: Width::try_from(32).unwrap(),
+            signed: false,
+            overflow: Overflow::Checked,
+            negative: Negative::Twos,
+            pad: Pad::Digits(16),
+            case: Case::Upper,
+            prefix: Prefix::Intel,
+            size_prefixes: SizePrefixes::Si,
+            all_rows: vec![Row::Signed, Row::Ruler],
+            layouts: BTreeMap::from([
+                (String::from("CR0"), "PE=0, MP=1, PG=31".parse().unwrap()),
+                (
+                    String::from("PTE"),
+                    "present=0, rw=1, pfn=12:51".parse().unwrap(),
+                ),
+            ]),
         };
         assert_eq!(config, expected_config);
     }
 
+    #[test]
+    fn test_override_signed() {
+        let mut config: Config = serde_yaml::from_str("signed: false").unwrap();
+        config.override_from_options(&Options::parse_from(["cork"]));
+        assert!(!config.signed);
+        config.override_from_options(&Options::parse_from(["cork", "--signed"]));
+        assert!(config.signed);
+        config.override_from_options(&Options::parse_from(["cork", "-u"]));
+        assert!(!config.signed);
+        assert!(Options::try_parse_from(["cork", "-s", "-u"]).is_err());
+    }
+
     #[test]
     fn test_config_deserialize_missing_values() {
         let config_str = "prompt: $
@@ -121,6 +271,18 @@ output_radix: Octal";
             header: default_header(),
             output_radix: FormatRadix::Octal,
             punctuate_output: false,
+            separator: Separator::default(),
+            group_sizes: GroupSizes::default(),
+            width: Width::default(),
+            signed: default_signed(),
+            overflow: Overflow::default(),
+            negative: Negative::default(),
+            pad: Pad::default(),
+            case: Case::default(),
+            prefix: Prefix::default(),
+            size_prefixes: SizePrefixes::default(),
+            all_rows: Vec::new(),
+            layouts: BTreeMap::new(),
         };
         assert_eq!(config, expected_config);
     }
@@ -134,7 +296,44 @@ output_radix: Octal";
             header: default_header(),
             output_radix: FormatRadix::default(),
             punctuate_output: false,
+            separator: Separator::default(),
+            group_sizes: GroupSizes::default(),
+            width: Width::default(),
+            signed: default_signed(),
+            overflow: Overflow::default(),
+            negative: Negative::default(),
+            pad: Pad::default(),
+            case: Case::default(),
+            prefix: Prefix::default(),
+            size_prefixes: SizePrefixes::default(),
+            all_rows: Vec::new(),
+            layouts: BTreeMap::new(),
         };
         assert_eq!(config, expected_config);
     }
+
+    #[test]
+    fn test_config_deserialize_pad() {
+        for (config_str, pad) in [
+            ("pad: true", Pad::Word),
+            ("pad: false", Pad::Off),
+            ("pad: 8", Pad::Digits(8)),
+        ] {
+            let config: Config = serde_yaml::from_str(config_str).unwrap();
+            assert_eq!(*config.pad(), pad);
+        }
+        assert!(serde_yaml::from_str::<Config>("pad: wide").is_err());
+    }
+
+    #[test]
+    fn test_config_deserialize_invalid_separator() {
+        assert!(serde_yaml::from_str::<Config>("separator: .").is_err());
+        assert!(serde_yaml::from_str::<Config>("separator: __").is_err());
+    }
+
+    #[test]
+    fn test_config_deserialize_invalid_width() {
+        let config_str = "width: 12";
+        assert!(serde_yaml::from_str::<Config>(config_str).is_err());
+    }
 }
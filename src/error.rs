@@ -1,4 +1,9 @@
 use crate::expression::PestRuleError;
+use crate::fixed::{QFormat, MAX_FIXED_BITS};
+use crate::float::FloatFormat;
+use crate::unit::Dim;
+use crate::word::Word;
+use std::ops::Range;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq, Eq)]
@@ -11,4 +16,75 @@ pub enum CorkError {
     InvalidKey(String),
     #[error("parsing error:\n{0}")]
     Parse(#[from] Box<PestRuleError>),
+    #[error("bit {index} is out of range for {word}")]
+    BitOutOfRange { index: String, word: Word },
+    #[error("{} overflows {word}", fmt_operation(.op, .operands))]
+    Overflow {
+        op: String,
+        operands: Vec<String>,
+        word: Word,
+    },
+    #[error("literal is out of range, it must fit in {max_bits} bits")]
+    LiteralOutOfRange { span: Range<usize>, max_bits: u64 },
+    #[error("literal overflows {word}, it must be from {} to {}", .word.decimal(.word.min()), .word.decimal(.word.max()))]
+    LiteralOverflow { span: Range<usize>, word: Word },
+    #[error("literal is out of range of {format}")]
+    FloatOutOfRange {
+        span: Range<usize>,
+        format: FloatFormat,
+    },
+    #[error(
+        "{format} is not a valid fixed-point format, it must be qN or qM.N with M + N up to {}",
+        MAX_FIXED_BITS
+    )]
+    InvalidFixedFormat { span: Range<usize>, format: String },
+    #[error("literal is out of range of {format}, it must be from {} to {}", .format.min(), .format.max())]
+    FixedOutOfRange { span: Range<usize>, format: QFormat },
+    #[error("literal rounds to 0 in {format}, whose smallest step is {}", .format.step())]
+    FixedUnderflow { span: Range<usize>, format: QFormat },
+    #[error("{op} cannot be applied to {}", fmt_dims(.dims))]
+    UnitMismatch { op: String, dims: Vec<Dim> },
+    #[error("base {base} is out of range, it must be from 2 to 36")]
+    InvalidBase { span: Range<usize>, base: String },
+    #[error("invalid layout: {0}")]
+    InvalidLayout(String),
+    #[error("{0} is not a layout")]
+    UnknownLayout(String),
+    #[error("{digit} is not a digit of base {base}")]
+    InvalidDigit {
+        span: Range<usize>,
+        digit: char,
+        base: u32,
+    },
+}
+
+impl CorkError {
+    /// Returns the part of the input line that the error is about, if it is known.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            CorkError::LiteralOutOfRange { span, .. }
+            | CorkError::LiteralOverflow { span, .. }
+            | CorkError::FloatOutOfRange { span, .. }
+            | CorkError::InvalidFixedFormat { span, .. }
+            | CorkError::FixedOutOfRange { span, .. }
+            | CorkError::FixedUnderflow { span, .. }
+            | CorkError::InvalidBase { span, .. }
+            | CorkError::InvalidDigit { span, .. } => Some(span.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Formats dimensions as "a time and a frequency".
+fn fmt_dims(dims: &[Dim]) -> String {
+    let dims: Vec<_> = dims.iter().map(Dim::to_string).collect();
+    dims.join(" and ")
+}
+
+/// Formats an operation as "lhs op rhs" for binary operators, or "op(operands)" otherwise.
+fn fmt_operation(op: &str, operands: &[String]) -> String {
+    match operands {
+        [lhs, rhs] if !op.starts_with(char::is_alphabetic) => format!("{} {} {}", lhs, op, rhs),
+        _ => format!("{}({})", op, operands.join(", ")),
+    }
 }
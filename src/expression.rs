@@ -1,14 +1,20 @@
 use crate::error::CorkError;
-use crate::format::FormatRadix;
+use crate::fixed::QFormat;
+use crate::float::FloatFormat;
+use crate::format::{Base, FormatRadix, SizePrefixes, Target, SIZE_PREFIXES};
+use crate::layout::Layout;
+use crate::unit::{Dim, Unit};
+use crate::value::Value;
+#[cfg(feature = "bignum")]
+use crate::value::MAX_BIG_BITS;
+use crate::word::{Overflow, Word};
 use once_cell::sync::Lazy;
 use pest::iterators::{Pair, Pairs};
 use pest::pratt_parser::PrattParser;
-use pest::Parser;
+use pest::{Parser, Span};
 use pest_derive::Parser;
 use std::fmt;
-use std::i64;
-use std::num::ParseIntError;
-use std::ops::Index;
+use std::ops::{Index, Range};
 use std::str::FromStr;
 
 #[derive(Parser)]
@@ -18,15 +24,86 @@ struct CommandParser;
 use pest::error::Error as PestError;
 pub(crate) type PestRuleError = PestError<Rule>;
 
-/// An Expr is either a node (which corresponds to a binary operation) or a leaf (which corresponds
-/// to a number).
+/// An Expr is either a node (which corresponds to a unary or binary operation) or a leaf
+/// (which corresponds to a number).
 #[derive(Debug, PartialEq, Eq)]
 pub enum Expr {
+    UnOp(UnOpExpr),
     BinOp(BinOpExpr),
-    Num(i64),
+    Literal(LiteralExpr),
+    Var(String),
+    Call(CallExpr),
+    Slice(SliceExpr),
+    Cond(CondExpr),
+    Size(SizeExpr),
+    Quantity(QuantityExpr),
     Ans,
 }
 
+/// A LiteralExpr is a number written out in the line, such as 0xff or 'ELF', along with the
+/// span where it was written. Its value is never negative: without big numbers, it is the
+/// bit-pattern of a u128.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LiteralExpr {
+    value: Value,
+    span: Range<usize>,
+}
+
+/// A QuantityExpr is a time or a frequency written with a unit, such as 10ms or 48MHz.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QuantityExpr {
+    num: u64,
+    unit: Unit,
+}
+
+/// A SizeExpr is a number of bytes written with a unit, such as 4K or 2MiB.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SizeExpr {
+    num: u64,
+    /// The power of the multiple of the unit, such as 2 for M.
+    power: u32,
+    /// Whether the unit is binary whatever the size prefixes are, as KiB is.
+    binary: bool,
+}
+
+impl SizeExpr {
+    /// Returns the number of bytes of the unit, reading a unit without an "i" as prefixes says.
+    pub fn multiple(&self, prefixes: SizePrefixes) -> u128 {
+        let prefixes = if self.binary {
+            SizePrefixes::Binary
+        } else {
+            prefixes
+        };
+        prefixes.multiple(self.power)
+    }
+}
+
+/// An UnOp is a prefix unary operator.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnOp {
+    Neg,
+    Not,
+    LogicalNot,
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "~",
+            UnOp::LogicalNot => "!",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// An UnOpExpr is an expr which has a single operand and a prefix operator.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnOpExpr {
+    expr: Box<Expr>,
+    op: UnOp,
+}
+
 /// An Op is a binary operator.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Op {
@@ -40,11 +117,27 @@ pub enum Op {
     Xor,
     LShift,
     RShift,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    LogicalAnd,
+    LogicalOr,
+    Pow,
+    At,
 }
 
 #[derive(Debug)]
 pub struct ParseOpError(String);
 
+impl fmt::Display for ParseOpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 impl FromStr for Op {
     type Err = ParseOpError;
 
@@ -60,11 +153,80 @@ impl FromStr for Op {
             "^" => Ok(Op::Xor),
             "<<" => Ok(Op::LShift),
             ">>" => Ok(Op::RShift),
+            "==" => Ok(Op::Eq),
+            "!=" => Ok(Op::Ne),
+            "<" => Ok(Op::Lt),
+            "<=" => Ok(Op::Le),
+            ">" => Ok(Op::Gt),
+            ">=" => Ok(Op::Ge),
+            "&&" => Ok(Op::LogicalAnd),
+            "||" => Ok(Op::LogicalOr),
+            "**" => Ok(Op::Pow),
+            "@" => Ok(Op::At),
             _ => Err(ParseOpError(format!("{} is not an Op", s))),
         }
     }
 }
 
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+            Op::Rem => "%",
+            Op::And => "&",
+            Op::Or => "|",
+            Op::Xor => "^",
+            Op::LShift => "<<",
+            Op::RShift => ">>",
+            Op::Eq => "==",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::LogicalAnd => "&&",
+            Op::LogicalOr => "||",
+            Op::Pow => "**",
+            Op::At => "@",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// A CallExpr is a call to a function, with an expr for each argument.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CallExpr {
+    name: String,
+    args: Vec<Expr>,
+}
+
+/// A BitRange selects the bits from hi down to lo, both inclusive.
+/// Without a lo, it selects the single bit hi.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BitRange {
+    hi: Box<Expr>,
+    lo: Option<Box<Expr>>,
+}
+
+/// A SliceExpr extracts a range of bits from an expr, of the form "expr[hi:lo]" or "expr[bit]".
+#[derive(Debug, PartialEq, Eq)]
+pub struct SliceExpr {
+    expr: Box<Expr>,
+    range: BitRange,
+}
+
+/// A CondExpr is a conditional of the form "cond ? if_true : if_false".
+/// Only the branch that is picked by cond is evaluated.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CondExpr {
+    cond: Box<Expr>,
+    if_true: Box<Expr>,
+    if_false: Box<Expr>,
+}
+
 /// A BinOpExpr is an expr which has two operands and an operator.
 /// The two operands might also be expressions.
 #[derive(Debug, Eq)]
@@ -86,6 +248,32 @@ pub struct SetDirective {
     args: Vec<String>,
 }
 
+impl SetDirective {
+    pub fn key(&self) -> &str {
+        &self.args[0]
+    }
+
+    /// Returns the value of a directive of the form "set key value".
+    pub fn value(&self) -> Result<&str, CorkError> {
+        match &self.args[..] {
+            [_, value] => Ok(value),
+            _ => Err(self.invalid_value()),
+        }
+    }
+
+    /// Returns all the values of the directive, that is, the words after the key.
+    pub fn values(&self) -> &[String] {
+        &self.args[1..]
+    }
+
+    pub fn invalid_value(&self) -> CorkError {
+        CorkError::InvalidValueForKey {
+            key: self.args[0].clone(),
+            value: self.args[1..].join(" "),
+        }
+    }
+}
+
 impl fmt::Display for SetDirective {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "set {}", self.args.join(" "))
@@ -100,37 +288,157 @@ impl Index<usize> for SetDirective {
     }
 }
 
-/// A ConvDirective is a command of the form "to hex|dec|bin|oct|dec".
+/// A ConvDirective is a command of the form "expr to target", where target is one of
+/// hex, dec, oct, bin, base(N), all, bits [msb|lsb], ascii or str.
 #[derive(Debug, PartialEq, Eq)]
 pub struct ConvDirective {
     expr: Expr,
-    radix: FormatRadix,
+    target: Target,
 }
 
 impl ConvDirective {
-    pub fn value(&self, ans: i64) -> Result<i64, CorkError> {
-        eval::eval_expr(&self.expr, ans)
+    /// Returns the value of the expression, checking that it is a quantity that the target
+    /// can show. A plain number is taken to be in nanoseconds or hertz.
+    pub fn value(&self, env: &eval::Env) -> Result<Value, CorkError> {
+        self.dim(env)?;
+        eval::eval_expr(&self.expr, env)
     }
 
-    pub fn radix(&self) -> FormatRadix {
-        self.radix
+    /// Checks that the value can be converted to the target, and returns its dimension
+    /// once converted. A plain number converted to a unit is read as nanoseconds or hertz.
+    pub fn dim(&self, env: &eval::Env) -> Result<Dim, CorkError> {
+        let dim = eval::unit_dim(&self.expr, env)?;
+        let mismatch = |target: &dyn fmt::Display| CorkError::UnitMismatch {
+            op: format!("to {}", target),
+            dims: vec![dim],
+        };
+        match self.target {
+            Target::Unit(unit) if dim == Dim::Count || dim.common(unit.dim()).is_some() => {
+                Ok(unit.dim())
+            }
+            Target::Unit(unit) => Err(mismatch(&unit)),
+            Target::Ticks => dim.common(Dim::Count).ok_or_else(|| mismatch(&"ticks")),
+            _ => Ok(dim),
+        }
+    }
+
+    pub fn target(&self) -> Target {
+        self.target
     }
 }
 
 impl fmt::Display for ConvDirective {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "convert to {}", &self.radix)
+        match self.target {
+            Target::Radix(radix) => write!(f, "convert to {}", radix),
+            Target::All => write!(f, "convert to all radices"),
+            Target::Bits(_) => write!(f, "convert to bits"),
+            Target::Bytes(_) => write!(f, "convert to bytes"),
+            Target::Float(float) => write!(f, "convert to {}", float),
+            Target::Fixed(q) => write!(f, "convert to {}", q),
+            Target::Size => write!(f, "convert to a size"),
+            Target::Unit(unit) => write!(f, "convert to {}", unit),
+            Target::Ticks => write!(f, "convert to ticks"),
+            Target::Ascii => write!(f, "convert to ASCII"),
+            Target::Str => write!(f, "convert to a string"),
+        }
+    }
+}
+
+/// A DecodeDirective is a command of the form "expr as layout", which shows the fields
+/// of the value of expr.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DecodeDirective {
+    expr: Expr,
+    layout: String,
+}
+
+impl DecodeDirective {
+    pub fn value(&self, env: &eval::Env) -> Result<Value, CorkError> {
+        eval::eval_expr(&self.expr, env)
+    }
+
+    /// Returns the dimension of the value.
+    pub fn dim(&self, env: &eval::Env) -> Result<Dim, CorkError> {
+        eval::unit_dim(&self.expr, env)
+    }
+
+    /// Returns the layout to decode the value with.
+    pub fn layout<'a>(&self, env: &'a eval::Env) -> Result<&'a Layout, CorkError> {
+        env.layout(&self.layout)
+            .ok_or_else(|| CorkError::UnknownLayout(self.layout.clone()))
+    }
+}
+
+/// A LayoutDefinition is a command of the form "layout name: field=bit, field=lo:hi, ...".
+#[derive(Debug, PartialEq, Eq)]
+pub struct LayoutDefinition {
+    name: String,
+    layout: Layout,
+}
+
+impl LayoutDefinition {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+}
+
+/// An Assignment is a command of the form "[let] name = expr".
+/// It may also assign to a range of bits of a variable, as in "name[hi:lo] = expr".
+#[derive(Debug, PartialEq, Eq)]
+pub struct Assignment {
+    name: String,
+    range: Option<BitRange>,
+    expr: Expr,
+}
+
+impl Assignment {
+    pub fn name(&self) -> &str {
+        &self.name
     }
+
+    /// Returns the value of the variable after the assignment.
+    pub fn value(&self, env: &eval::Env) -> Result<Value, CorkError> {
+        eval::eval_assignment(self, env)
+    }
+
+    /// Returns the dimension of the value that the variable is given. Assigning to bits
+    /// of a variable keeps its dimension.
+    pub fn dim(&self, env: &eval::Env) -> Result<Dim, CorkError> {
+        let dim = eval::unit_dim(&self.expr, env)?;
+        match self.range {
+            Some(_) => Ok(env.var_dim(&self.name).unwrap_or_default()),
+            None => Ok(dim),
+        }
+    }
+}
+
+/// A Function is a user-defined function, of the form "fn name(params) = body".
+#[derive(Debug, PartialEq, Eq)]
+pub struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Expr,
 }
 
 /// A Command is a one line worth of input from the user.
-/// It can either be a SetDirective or an Expr.
+/// It can either be a SetDirective, an Assignment, a Function or Layout definition or an Expr.
+/// The "vars" command lists all variables.
 /// As an escape-hatch, there is also an empty command.
 #[derive(Debug, PartialEq, Eq)]
 pub enum Command {
     Expr(Expr),
     Set(SetDirective),
     Convert(ConvDirective),
+    Decode(DecodeDirective),
+    Assign(Assignment),
+    Define(Function),
+    DefineLayout(LayoutDefinition),
+    Vars,
     Empty,
 }
 
@@ -145,26 +453,67 @@ pub fn parse_line<T: AsRef<str>>(line: T) -> Result<Command, CorkError> {
     if comm.is_none() {
         return Ok(Command::Empty);
     }
-    Ok(parse_comm(comm.unwrap().into_inner().next().unwrap()))
+    parse_comm(comm.unwrap().into_inner().next().unwrap())
 }
 
-fn parse_comm(pair: Pair<Rule>) -> Command {
-    match pair.as_rule() {
-        Rule::expr => Command::Expr(parse_expr(pair.into_inner())),
+fn parse_comm(pair: Pair<Rule>) -> Result<Command, CorkError> {
+    let command = match pair.as_rule() {
+        Rule::expr => Command::Expr(parse_expr(pair.into_inner())?),
         Rule::set_directive => Command::Set(SetDirective {
             args: pair.as_str().split(' ').skip(1).map(String::from).collect(),
         }),
         Rule::tor_directive => {
             let mut pairs = pair.into_inner();
             let expr_pair = pairs.next().unwrap();
-            let radix_pair = pairs.next().unwrap();
+            let target_pair = pairs.next().unwrap();
             Command::Convert(ConvDirective {
-                expr: parse_expr(expr_pair.into_inner()),
-                radix: parse_radix(radix_pair),
+                expr: parse_expr(expr_pair.into_inner())?,
+                target: parse_target(target_pair)?,
             })
         }
+        Rule::as_directive => {
+            let mut pairs = pair.into_inner();
+            let expr_pair = pairs.next().unwrap();
+            let layout_pair = pairs.next().unwrap();
+            Command::Decode(DecodeDirective {
+                expr: parse_expr(expr_pair.into_inner())?,
+                layout: layout_pair.as_str().to_string(),
+            })
+        }
+        Rule::layout_directive => {
+            let mut pairs = pair.into_inner();
+            let name = pairs.next().unwrap().as_str().to_string();
+            let fields = pairs.next().unwrap().as_str();
+            Command::DefineLayout(LayoutDefinition {
+                name,
+                layout: fields.parse().map_err(CorkError::InvalidLayout)?,
+            })
+        }
+        Rule::assignment => {
+            let mut pairs: Vec<_> = pair.into_inner().collect();
+            let expr_pair = pairs.pop().unwrap();
+            let mut pairs = pairs.into_iter();
+            let name_pair = pairs.next().unwrap();
+            Command::Assign(Assignment {
+                name: name_pair.as_str().to_string(),
+                range: pairs.next().map(parse_bit_range).transpose()?,
+                expr: parse_expr(expr_pair.into_inner())?,
+            })
+        }
+        Rule::function => {
+            let mut pairs: Vec<_> = pair.into_inner().collect();
+            let body_pair = pairs.pop().unwrap();
+            let mut names = pairs.into_iter().map(|p| p.as_str().to_string());
+            Command::Define(Function {
+                name: names.next().unwrap(),
+                params: names.collect(),
+                body: parse_expr(body_pair.into_inner())?,
+            })
+        }
+        Rule::vars_directive => Command::Vars,
         _ => unreachable!(),
-    }
+    };
+    Ok(command)
 }
 
 static PRATT_PARSER: Lazy<PrattParser<Rule>> = Lazy::new(|| {
@@ -175,64 +524,397 @@ static PRATT_PARSER: Lazy<PrattParser<Rule>> = Lazy::new(|| {
     // Operators at the same level have same precedence
     // The "Left" indicates that the operators associate to the left
     PrattParser::new()
+        .op(Op::infix(ternary, Right))
+        .op(Op::infix(logical_or, Left))
+        .op(Op::infix(logical_and, Left))
         .op(Op::infix(or, Left))
         .op(Op::infix(xor, Left))
         .op(Op::infix(and, Left))
+        .op(Op::infix(eq, Left) | Op::infix(ne, Left))
+        .op(Op::infix(lt, Left) | Op::infix(le, Left) | Op::infix(gt, Left) | Op::infix(ge, Left))
         .op(Op::infix(lshift, Left) | Op::infix(rshift, Left))
+        .op(Op::infix(at, Left))
         .op(Op::infix(add, Left) | Op::infix(subtract, Left))
         .op(Op::infix(multiply, Left) | Op::infix(divide, Left) | Op::infix(rem, Left))
+        .op(Op::prefix(negate) | Op::prefix(not) | Op::prefix(logical_not))
+        // "**" binds tighter than the prefix operators, so "-2 ** 2" is -4
+        .op(Op::infix(pow, Right))
+        .op(Op::postfix(slice))
 });
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum Radix {
-    Bin,
-    Oct,
-    Dec,
-    Hex,
+fn parse_target(p: Pair<Rule>) -> Result<Target, CorkError> {
+    let radix = match p.as_str() {
+        "dec" => FormatRadix::Decimal,
+        "oct" => FormatRadix::Octal,
+        "hex" => FormatRadix::Hex,
+        "bin" => FormatRadix::Binary,
+        "all" => return Ok(Target::All),
+        "ascii" => return Ok(Target::Ascii),
+        "str" => return Ok(Target::Str),
+        "size" => return Ok(Target::Size),
+        "ticks" => return Ok(Target::Ticks),
+        "ns" | "us" | "ms" | "s" | "Hz" | "kHz" | "MHz" => {
+            return Ok(Target::Unit(p.as_str().parse().unwrap()))
+        }
+        "f16" | "bf16" | "f32" | "f64" => return Ok(Target::Float(p.as_str().parse().unwrap())),
+        q if q.starts_with('q') => {
+            let span = p.as_span();
+            return Ok(Target::Fixed(parse_q_format(q, span.start()..span.end())?));
+        }
+        bits if bits.starts_with("bits") => {
+            let order = p
+                .into_inner()
+                .next()
+                .map(|order| order.as_str().parse().unwrap());
+            return Ok(Target::Bits(order.unwrap_or_default()));
+        }
+        bytes if bytes.starts_with("bytes") => {
+            let endian = p
+                .into_inner()
+                .next()
+                .map(|endian| endian.as_str().parse().unwrap());
+            return Ok(Target::Bytes(endian.unwrap_or_default()));
+        }
+        _ => {
+            let base = p.into_inner().next().unwrap();
+            let span = base.as_span();
+            parse_base(base.as_str(), span.start()..span.end())?.into()
+        }
+    };
+    Ok(Target::Radix(radix))
+}
+
+/// parse_base parses the base of a based literal or radix, found at span of the line.
+fn parse_base(base: &str, span: Range<usize>) -> Result<Base, CorkError> {
+    base.parse().map_err(|_| CorkError::InvalidBase {
+        span,
+        base: base.to_string(),
+    })
+}
+
+/// parse_q_format returns the fixed-point format named by format, at span of the line.
+fn parse_q_format(format: &str, span: Range<usize>) -> Result<QFormat, CorkError> {
+    format.parse().map_err(|_| CorkError::InvalidFixedFormat {
+        span,
+        format: format.to_string(),
+    })
+}
+
+/// The maximum number of bits in a literal.
+#[cfg(not(feature = "bignum"))]
+const MAX_LITERAL_BITS: u64 = 128;
+#[cfg(feature = "bignum")]
+const MAX_LITERAL_BITS: u64 = MAX_BIG_BITS;
+
+/// parse_num parses a literal into the bit-pattern of the number, so that literals
+/// up to u128::MAX are accepted. They are truncated to the word during evaluation.
+/// Returns None if the literal is larger than that.
+#[cfg(not(feature = "bignum"))]
+fn parse_num(digits: &str, radix: u32) -> Option<Value> {
+    let num_str = digits.replace(['_', '\''], "");
+    u128::from_str_radix(&num_str, radix)
+        .ok()
+        .map(|num| Value::Fixed(num as i128))
+}
+
+/// parse_num parses a literal into a fixed value if it fits an i128, and into a big value
+/// otherwise. They are truncated to the word during evaluation, unless the width is big.
+/// Returns None if the literal has more than MAX_BIG_BITS bits.
+#[cfg(feature = "bignum")]
+fn parse_num(digits: &str, radix: u32) -> Option<Value> {
+    let num_str = digits.replace(['_', '\''], "");
+    if let Ok(num) = i128::from_str_radix(&num_str, radix) {
+        return Some(Value::Fixed(num));
+    }
+    num_bigint::BigInt::parse_bytes(num_str.as_bytes(), radix)
+        .filter(|num| num.bits() <= MAX_BIG_BITS)
+        .map(Value::Big)
+}
+
+fn parse_literal(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let s = literal.as_str();
+    let span = literal.as_span();
+    let (radix, digits) = match literal.as_rule() {
+        Rule::dec => (10, s),
+        Rule::hex => (16, &s[2..]),
+        Rule::oct => (8, &s[2..]),
+        Rule::bin => (2, &s[2..]),
+        Rule::based => parse_based(s, span.start())?,
+        rule => unreachable!("expected literal rule, found {:?}", rule),
+    };
+    let span = span.start()..span.end();
+    match parse_num(digits, radix) {
+        Some(value) => Ok(Expr::Literal(LiteralExpr { value, span })),
+        None => Err(CorkError::LiteralOutOfRange {
+            span,
+            max_bits: MAX_LITERAL_BITS,
+        }),
+    }
+}
+
+/// parse_sized parses a number with a size unit, such as "4K". The number must fit in 64 bits,
+/// so that the size fits in 128.
+fn parse_sized(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let mut pairs = literal.into_inner();
+    let num = pairs.next().unwrap().as_str().replace(['_', '\''], "");
+    let unit = pairs.next().unwrap().as_str();
+    let num = num.parse().map_err(|_| CorkError::LiteralOutOfRange {
+        span: span.start()..span.end(),
+        max_bits: 64,
+    })?;
+    let prefix = unit.chars().next().unwrap().to_ascii_uppercase();
+    let power = SIZE_PREFIXES.iter().position(|&p| p == prefix).unwrap() as u32 + 1;
+    Ok(Expr::Size(SizeExpr {
+        num,
+        power,
+        binary: unit.contains('i'),
+    }))
 }
 
-impl Radix {
-    fn numeric_radix(&self) -> u32 {
-        match self {
-            Radix::Bin => 2,
-            Radix::Oct => 8,
-            Radix::Dec => 10,
-            Radix::Hex => 16,
+/// parse_quantity parses a number with a unit of time or frequency, such as "10ms". The number
+/// must fit in 64 bits, so that it fits in 128 once it is in nanoseconds or hertz.
+fn parse_quantity(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let mut pairs = literal.into_inner();
+    let num = pairs.next().unwrap().as_str().replace(['_', '\''], "");
+    let num = num.parse().map_err(|_| CorkError::LiteralOutOfRange {
+        span: span.start()..span.end(),
+        max_bits: 64,
+    })?;
+    let unit = pairs.next().unwrap().as_str().parse().unwrap();
+    Ok(Expr::Quantity(QuantityExpr { num, unit }))
+}
+
+/// parse_chars packs the bytes of a character literal into a number, the first byte being
+/// the most significant unless the literal ends in "le". Characters are encoded in UTF-8.
+fn parse_chars(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let s = literal.as_str();
+    let (body, little) = match s.strip_suffix("le") {
+        Some(body) => (body, true),
+        None => (s.strip_suffix("be").unwrap_or(s), false),
+    };
+    let mut bytes = Vec::new();
+    let mut chars = body[1..body.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
         }
+        let byte = match chars.next().unwrap() {
+            'x' => {
+                let hex = String::from_iter([chars.next().unwrap(), chars.next().unwrap()]);
+                u8::from_str_radix(&hex, 16).unwrap()
+            }
+            'n' => b'\n',
+            'r' => b'\r',
+            't' => b'\t',
+            '0' => 0,
+            c => c as u8,
+        };
+        bytes.push(byte);
     }
+    pack_literal(bytes, little, span)
 }
 
-fn parse_radix(p: Pair<Rule>) -> FormatRadix {
-    match p.as_str() {
-        "dec" => FormatRadix::Decimal,
-        "oct" => FormatRadix::Octal,
-        "hex" => FormatRadix::Hex,
-        "bin" => FormatRadix::Binary,
-        _ => unreachable!(),
+/// parse_byte_list packs the bytes of a literal such as "bytes(de ad be ef)" into a number,
+/// the first byte being the most significant unless the literal ends in "le".
+fn parse_byte_list(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let mut bytes = Vec::new();
+    let mut little = false;
+    for pair in literal.into_inner() {
+        match pair.as_rule() {
+            Rule::hex_byte => bytes.push(u8::from_str_radix(pair.as_str(), 16).unwrap()),
+            Rule::endian => little = pair.as_str() == "le",
+            rule => unreachable!("expected byte or endian, found {:?}", rule),
+        }
+    }
+    pack_literal(bytes, little, span)
+}
+
+/// parse_float returns the bits of a float literal, such as "f32(1.5)", as a number.
+fn parse_float(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let span = span.start()..span.end();
+    let mut pairs = literal.into_inner();
+    let format: FloatFormat = pairs.next().unwrap().as_str().parse().unwrap();
+    let digits = pairs.next().unwrap().as_str();
+    match format.encode(digits) {
+        Some(bits) => Ok(Expr::Literal(LiteralExpr {
+            value: Value::Fixed(bits as i128),
+            span,
+        })),
+        None => Err(CorkError::FloatOutOfRange { span, format }),
+    }
+}
+
+/// parse_fixed returns a fixed-point literal, such as "q15(0.5)", as an integer scaled by
+/// the fraction bits of its format. It is an error for the literal to saturate the format,
+/// or to round to 0 when it is not 0. A negative number is the negation of a literal, as
+/// "-0x8000" is, so that it is checked against the word like any other literal.
+fn parse_fixed(literal: Pair<Rule>) -> Result<Expr, CorkError> {
+    let span = literal.as_span();
+    let mut pairs = literal.into_inner();
+    let format = pairs.next().unwrap();
+    let format_span = format.as_span();
+    let format = parse_q_format(format.as_str(), format_span.start()..format_span.end())?;
+    let digits = pairs.next().unwrap().as_str();
+    let span = span.start()..span.end();
+    match format.encode(digits) {
+        None => Err(CorkError::FixedOutOfRange { span, format }),
+        Some(0) if digits.contains(|c: char| ('1'..='9').contains(&c)) => {
+            Err(CorkError::FixedUnderflow { span, format })
+        }
+        Some(num) => {
+            let literal = Expr::Literal(LiteralExpr {
+                value: Value::Fixed(num.unsigned_abs() as i128),
+                span,
+            });
+            if num >= 0 {
+                return Ok(literal);
+            }
+            Ok(Expr::UnOp(UnOpExpr {
+                expr: Box::new(literal),
+                op: UnOp::Neg,
+            }))
+        }
+    }
+}
+
+/// pack_literal packs the bytes of the literal at span into a number, reversing them first
+/// if they are little-endian.
+fn pack_literal(mut bytes: Vec<u8>, little: bool, span: Span) -> Result<Expr, CorkError> {
+    if little {
+        bytes.reverse();
+    }
+    let span = span.start()..span.end();
+    match pack_bytes(&bytes) {
+        Some(value) => Ok(Expr::Literal(LiteralExpr { value, span })),
+        None => Err(CorkError::LiteralOutOfRange {
+            span,
+            max_bits: MAX_LITERAL_BITS,
+        }),
+    }
+}
+
+/// pack_bytes reads bytes as a number, most significant first. Like parse_num, it returns
+/// None if the number has more than MAX_LITERAL_BITS bits.
+#[cfg(not(feature = "bignum"))]
+fn pack_bytes(bytes: &[u8]) -> Option<Value> {
+    if bytes.len() > 16 {
+        return None;
     }
+    let num = bytes.iter().fold(0, |num, &byte| (num << 8) | byte as u128);
+    Some(Value::Fixed(num as i128))
 }
 
-fn parse_num(mut s: &str, radix: Radix) -> Result<i64, ParseIntError> {
-    if radix != Radix::Dec {
-        s = &s[2..];
+#[cfg(feature = "bignum")]
+fn pack_bytes(bytes: &[u8]) -> Option<Value> {
+    let num = num_bigint::BigInt::from_bytes_be(num_bigint::Sign::Plus, bytes);
+    if num.bits() > MAX_BIG_BITS {
+        return None;
+    }
+    Some(i128::try_from(&num).map_or(Value::Big(num), Value::Fixed))
+}
+
+/// parse_based splits a based literal found at start of the line into its radix and digits,
+/// checking that each digit belongs to the radix.
+fn parse_based(s: &str, start: usize) -> Result<(u32, &str), CorkError> {
+    let (offset, base, digits) = match s.strip_prefix("0r") {
+        Some(rest) => {
+            let (base, digits) = rest.split_once(':').unwrap();
+            (2, base, digits)
+        }
+        None => {
+            let (base, digits) = s.split_once('#').unwrap();
+            (0, base, digits.strip_suffix('#').unwrap_or(digits))
+        }
+    };
+    let base_start = start + offset;
+    let radix = parse_base(base, base_start..base_start + base.len())?.radix();
+    let digits_start = base_start + base.len() + 1;
+    let invalid = digits
+        .char_indices()
+        .find(|&(_, c)| c.is_ascii_alphanumeric() && !c.is_digit(radix));
+    if let Some((i, digit)) = invalid {
+        return Err(CorkError::InvalidDigit {
+            span: digits_start + i..digits_start + i + 1,
+            digit,
+            base: radix,
+        });
     }
-    let num_str = s.replace('_', "");
-    i64::from_str_radix(&num_str, radix.numeric_radix())
+    Ok((radix, digits))
+}
+
+fn parse_bit_range(slice: Pair<Rule>) -> Result<BitRange, CorkError> {
+    let mut pairs = slice.into_inner();
+    let hi = parse_expr(pairs.next().unwrap().into_inner())?;
+    let lo = match pairs.next() {
+        Some(lo) => Some(Box::new(parse_expr(lo.into_inner())?)),
+        None => None,
+    };
+    Ok(BitRange {
+        hi: Box::new(hi),
+        lo,
+    })
 }
 
-fn parse_expr(expression: Pairs<Rule>) -> Expr {
+/// parse_expr builds an Expr out of expression, failing only if a literal is out of range.
+fn parse_expr(expression: Pairs<Rule>) -> Result<Expr, CorkError> {
     PRATT_PARSER
         .map_primary(|primary| match primary.as_rule() {
             Rule::number => parse_expr(primary.into_inner()),
-            Rule::dec => Expr::Num(parse_num(primary.as_str(), Radix::Dec).unwrap()),
-            Rule::hex => Expr::Num(parse_num(primary.as_str(), Radix::Hex).unwrap()),
-            Rule::oct => Expr::Num(parse_num(primary.as_str(), Radix::Oct).unwrap()),
-            Rule::bin => Expr::Num(parse_num(primary.as_str(), Radix::Bin).unwrap()),
-            Rule::ans => Expr::Ans,
+            Rule::dec | Rule::hex | Rule::oct | Rule::bin | Rule::based => parse_literal(primary),
+            Rule::sized => parse_sized(primary),
+            Rule::quantity => parse_quantity(primary),
+            Rule::chars => parse_chars(primary),
+            Rule::byte_list => parse_byte_list(primary),
+            Rule::float => parse_float(primary),
+            Rule::fixed => parse_fixed(primary),
+            Rule::ans => Ok(Expr::Ans),
+            Rule::ident => Ok(Expr::Var(primary.as_str().to_string())),
+            Rule::call => {
+                let mut pairs = primary.into_inner();
+                let name = pairs.next().unwrap().as_str().to_string();
+                let args = pairs
+                    .map(|arg| parse_expr(arg.into_inner()))
+                    .collect::<Result<_, _>>()?;
+                Ok(Expr::Call(CallExpr { name, args }))
+            }
             Rule::expr => parse_expr(primary.into_inner()),
             rule => unreachable!("parse_expr expected atom, found {:?}", rule),
         })
+        .map_prefix(|op, rhs| {
+            let op = match op.as_rule() {
+                Rule::negate => UnOp::Neg,
+                Rule::not => UnOp::Not,
+                Rule::logical_not => UnOp::LogicalNot,
+                rule => unreachable!("expected prefix operator rule, found {:?}", rule),
+            };
+            Ok(Expr::UnOp(UnOpExpr {
+                expr: Box::new(rhs?),
+                op,
+            }))
+        })
+        .map_postfix(|lhs, op| match op.as_rule() {
+            Rule::slice => Ok(Expr::Slice(SliceExpr {
+                expr: Box::new(lhs?),
+                range: parse_bit_range(op)?,
+            })),
+            rule => unreachable!("expected postfix operator rule, found {:?}", rule),
+        })
         .map_infix(|lhs, op, rhs| {
+            if op.as_rule() == Rule::ternary {
+                let if_true = parse_expr(op.into_inner().next().unwrap().into_inner())?;
+                return Ok(Expr::Cond(CondExpr {
+                    cond: Box::new(lhs?),
+                    if_true: Box::new(if_true),
+                    if_false: Box::new(rhs?),
+                }));
+            }
             let op = match op.as_rule() {
                 Rule::add => Op::Add,
                 Rule::subtract => Op::Sub,
@@ -244,186 +926,2445 @@ fn parse_expr(expression: Pairs<Rule>) -> Expr {
                 Rule::xor => Op::Xor,
                 Rule::lshift => Op::LShift,
                 Rule::rshift => Op::RShift,
+                Rule::eq => Op::Eq,
+                Rule::ne => Op::Ne,
+                Rule::lt => Op::Lt,
+                Rule::le => Op::Le,
+                Rule::gt => Op::Gt,
+                Rule::ge => Op::Ge,
+                Rule::logical_and => Op::LogicalAnd,
+                Rule::logical_or => Op::LogicalOr,
+                Rule::pow => Op::Pow,
+                Rule::at => Op::At,
                 rule => unreachable!("expected operator rule, found {:?}", rule),
             };
-            Expr::BinOp(BinOpExpr {
-                left: Box::new(lhs),
-                right: Box::new(rhs),
+            Ok(Expr::BinOp(BinOpExpr {
+                left: Box::new(lhs?),
+                right: Box::new(rhs?),
                 op,
-            })
+            }))
         })
         .parse(expression)
 }
 
 pub mod eval {
     use super::*;
+    use getset::{CopyGetters, Getters, Setters};
+    use std::collections::{BTreeMap, HashMap};
 
-    pub fn eval_expr(expr: &Expr, ans: i64) -> Result<i64, CorkError> {
-        match &expr {
-            Expr::Num(num) => Ok(*num),
-            Expr::BinOp(expr) => {
-                let left = eval_expr(expr.left.as_ref(), ans)?;
-                let right = eval_expr(expr.right.as_ref(), ans)?;
-                match expr.op {
-                    // note that order does not matter here
-                    Op::Add => Ok(left + right),
-                    Op::Sub => Ok(left - right),
-                    Op::Mul => Ok(left * right),
-                    Op::And => Ok(left & right),
-                    Op::Xor => Ok(left ^ right),
-                    Op::Or => Ok(left | right),
-                    Op::LShift => Ok(left << right),
-                    Op::RShift => Ok(left >> right),
-                    Op::Div => {
-                        if right == 0 {
-                            Err(CorkError::Eval(String::from("Cannot divide by 0")))
-                        } else {
-                            Ok(left / right)
-                        }
-                    }
-                    Op::Rem => {
-                        if right == 0 {
-                            Err(CorkError::Eval(String::from("Cannot divide by 0")))
-                        } else {
-                            Ok(left % right)
-                        }
-                    }
+    /// The maximum depth of nested function calls, which stops runaway recursion.
+    pub const MAX_CALL_DEPTH: usize = 256;
+
+    /// An Env is the state that expressions are evaluated in.
+    /// It holds the last answer, the variables, the functions and the arithmetic settings.
+    #[derive(Debug, Default, Getters, CopyGetters, Setters)]
+    pub struct Env {
+        #[getset(get = "pub")]
+        ans: Value,
+        /// The dimension of ans, as times and frequencies are held as plain numbers.
+        #[getset(get_copy = "pub")]
+        ans_dim: Dim,
+        #[getset(get_copy = "pub", set = "pub")]
+        word: Word,
+        #[getset(get_copy = "pub", set = "pub")]
+        overflow: Overflow,
+        /// What the size prefixes of literals such as 4K stand for.
+        #[getset(get_copy = "pub", set = "pub")]
+        size_prefixes: SizePrefixes,
+        /// Whether numbers are unbounded, instead of being numbers of the word.
+        #[cfg(feature = "bignum")]
+        #[getset(get_copy = "pub", set = "pub")]
+        big: bool,
+        vars: BTreeMap<String, (Value, Dim)>,
+        functions: HashMap<String, Function>,
+        layouts: HashMap<String, Layout>,
+    }
+
+    impl Env {
+        pub fn new(word: Word, overflow: Overflow) -> Self {
+            Self {
+                word,
+                overflow,
+                ..Default::default()
+            }
+        }
+
+        pub fn set_ans(&mut self, ans: Value, dim: Dim) {
+            self.ans = ans;
+            self.ans_dim = dim;
+        }
+
+        pub fn var(&self, name: &str) -> Option<&Value> {
+            self.vars.get(name).map(|(value, _)| value)
+        }
+
+        /// Returns the dimension of the value of a variable.
+        pub fn var_dim(&self, name: &str) -> Option<Dim> {
+            self.vars.get(name).map(|&(_, dim)| dim)
+        }
+
+        pub fn set_var(&mut self, name: &str, value: Value, dim: Dim) {
+            self.vars.insert(name.to_string(), (value, dim));
+        }
+
+        /// Returns all variables, sorted by their names.
+        pub fn vars(&self) -> impl Iterator<Item = (&str, &Value)> {
+            self.vars
+                .iter()
+                .map(|(name, (value, _))| (name.as_str(), value))
+        }
+
+        /// Defines function, replacing any previous function of the same name.
+        /// Built-in functions cannot be redefined.
+        pub fn define(&mut self, function: Function) -> Result<(), CorkError> {
+            if builtin(&function.name).is_some() {
+                return Err(CorkError::Eval(format!(
+                    "{} is a built-in function",
+                    function.name
+                )));
+            }
+            // a call such as q8(1) would be read as a fixed-point literal
+            let digits = function.name.strip_prefix('q').unwrap_or_default();
+            if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(CorkError::Eval(format!(
+                    "{} is reserved for fixed-point literals",
+                    function.name
+                )));
+            }
+            // the parameters may be of any dimension, which each call is checked with
+            let scope = DimScope {
+                args: function
+                    .params
+                    .iter()
+                    .map(|param| (param.as_str(), Dim::Unknown))
+                    .collect(),
+                calls: vec![function.name.as_str()],
+            };
+            dim_in_scope(&function.body, self, &scope)?;
+            for (i, param) in function.params.iter().enumerate() {
+                if function.params[..i].contains(param) {
+                    return Err(CorkError::Eval(format!(
+                        "{} has more than one parameter named {}",
+                        function.name, param
+                    )));
                 }
             }
-            Expr::Ans => Ok(ans),
+            self.functions.insert(function.name.clone(), function);
+            Ok(())
+        }
+
+        pub fn layout(&self, name: &str) -> Option<&Layout> {
+            self.layouts.get(name)
+        }
+
+        /// Defines a layout, replacing any previous layout of the same name.
+        pub fn define_layout(&mut self, name: &str, layout: Layout) {
+            self.layouts.insert(name.to_string(), layout);
         }
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::eval::*;
-    use super::*;
+    /// A Builtin is a function provided by cork. It is called with its arguments
+    /// already evaluated, and always with as many arguments as its arity.
+    /// It returns None if its result overflows the word.
+    struct Builtin {
+        name: &'static str,
+        arity: usize,
+        eval: fn(&[i128], &Env) -> Result<Option<i128>, CorkError>,
+    }
 
-    #[test]
-    fn test_expr_parse() {
-        let expr1 = Expr::BinOp(BinOpExpr {
-            left: Box::new(Expr::BinOp(BinOpExpr {
-                left: Box::new(Expr::Num(5)),
-                right: Box::new(Expr::Num(6)),
-                op: Op::Add,
-            })),
-            right: Box::new(Expr::Num(2)),
-            op: Op::Mul,
+    /// The built-in functions, which operate on the bits of the word
+    /// or do integer math that follows the overflow policy.
+    static BUILTINS: &[Builtin] = &[
+        Builtin {
+            name: "popcnt",
+            arity: 1,
+            eval: |args, env| Ok(Some(env.word().bits_of(args[0]).count_ones() as i128)),
+        },
+        Builtin {
+            name: "clz",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                let zeros = word.bits_of(args[0]).leading_zeros() - (128 - word.bits());
+                Ok(Some(zeros as i128))
+            },
+        },
+        Builtin {
+            name: "ctz",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                Ok(Some(
+                    word.bits_of(args[0]).trailing_zeros().min(word.bits()) as i128,
+                ))
+            },
+        },
+        Builtin {
+            name: "bswap",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                let bits = word.bits_of(args[0]).swap_bytes() >> (128 - word.bits());
+                Ok(Some(word.value_of(bits)))
+            },
+        },
+        Builtin {
+            name: "bswap16",
+            arity: 1,
+            eval: |args, env| swap_low_bytes("bswap16", env.word(), args[0], 16),
+        },
+        Builtin {
+            name: "bswap32",
+            arity: 1,
+            eval: |args, env| swap_low_bytes("bswap32", env.word(), args[0], 32),
+        },
+        Builtin {
+            name: "bswap64",
+            arity: 1,
+            eval: |args, env| swap_low_bytes("bswap64", env.word(), args[0], 64),
+        },
+        Builtin {
+            name: "bitrev",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                let bits = word.bits_of(args[0]).reverse_bits() >> (128 - word.bits());
+                Ok(Some(word.value_of(bits)))
+            },
+        },
+        Builtin {
+            name: "rotl",
+            arity: 2,
+            eval: |args, env| Ok(Some(rotate_left(env.word(), args[0], args[1]))),
+        },
+        Builtin {
+            name: "rotr",
+            arity: 2,
+            eval: |args, env| Ok(Some(rotate_right(env.word(), args[0], args[1]))),
+        },
+        Builtin {
+            name: "parity",
+            arity: 1,
+            eval: |args, env| Ok(Some((env.word().bits_of(args[0]).count_ones() & 1) as i128)),
+        },
+        Builtin {
+            name: "setbits",
+            arity: 4,
+            eval: |args, env| {
+                let word = env.word();
+                let (hi, lo) = bit_range(word, args[1], args[2])?;
+                Ok(Some(insert_bits(word, args[0], hi, lo, args[3])))
+            },
+        },
+        Builtin {
+            name: "pow",
+            arity: 2,
+            eval: |args, env| {
+                let exp = exponent(env.word(), args[1])?;
+                Ok(env.word().pow(args[0], exp, env.overflow()))
+            },
+        },
+        Builtin {
+            name: "gcd",
+            arity: 2,
+            eval: |args, env| {
+                let word = env.word();
+                let gcd = gcd(magnitude(word, args[0]), magnitude(word, args[1]));
+                Ok(word.fit_unsigned(gcd, env.overflow()))
+            },
+        },
+        Builtin {
+            name: "lcm",
+            arity: 2,
+            eval: |args, env| {
+                let word = env.word();
+                let (a, b) = (magnitude(word, args[0]), magnitude(word, args[1]));
+                if a == 0 || b == 0 {
+                    return Ok(Some(0));
+                }
+                let quotient = a / gcd(a, b);
+                let lcm = match (quotient.checked_mul(b), env.overflow()) {
+                    (Some(lcm), overflow) => word.fit_unsigned(lcm, overflow),
+                    (None, Overflow::Checked) => None,
+                    (None, Overflow::Wrapping) => Some(word.wrap(quotient.wrapping_mul(b) as i128)),
+                    (None, Overflow::Saturating) => Some(word.max()),
+                };
+                Ok(lcm)
+            },
+        },
+        Builtin {
+            name: "isqrt",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                if word.cmp(args[0], 0).is_lt() {
+                    return Err(CorkError::Eval(String::from(
+                        "isqrt is not defined for negative numbers",
+                    )));
+                }
+                Ok(Some(word.bits_of(args[0]).isqrt() as i128))
+            },
+        },
+        Builtin {
+            name: "log2",
+            arity: 1,
+            eval: |args, env| {
+                let word = env.word();
+                if word.cmp(args[0], 0).is_le() {
+                    return Err(CorkError::Eval(String::from(
+                        "log2 is only defined for positive numbers",
+                    )));
+                }
+                Ok(Some(word.bits_of(args[0]).ilog2() as i128))
+            },
+        },
+        Builtin {
+            name: "align_up",
+            arity: 2,
+            eval: |args, env| {
+                let word = env.word();
+                let rem = alignment_rem(word, "align_up", args[0], args[1])?;
+                if rem == 0 {
+                    return Ok(Some(args[0]));
+                }
+                // the alignment may not fit in an i128 in an unsigned word, but the gap does
+                Ok(word.add(args[0], args[1].wrapping_sub(rem), env.overflow()))
+            },
+        },
+        Builtin {
+            name: "align_down",
+            arity: 2,
+            eval: |args, env| {
+                let word = env.word();
+                let rem = alignment_rem(word, "align_down", args[0], args[1])?;
+                Ok(word.sub(args[0], rem, env.overflow()))
+            },
+        },
+    ];
+
+    fn builtin(name: &str) -> Option<&'static Builtin> {
+        BUILTINS.iter().find(|builtin| builtin.name == name)
+    }
+
+    /// Returns the absolute value of num, as an unsigned number.
+    fn magnitude(word: Word, num: i128) -> u128 {
+        if word.signed() {
+            num.unsigned_abs()
+        } else {
+            num as u128
+        }
+    }
+
+    fn gcd(mut a: u128, mut b: u128) -> u128 {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    }
+
+    fn exponent(word: Word, exp: i128) -> Result<u128, CorkError> {
+        if word.cmp(exp, 0).is_lt() {
+            Err(CorkError::Eval(String::from(
+                "Cannot raise to a negative power",
+            )))
+        } else {
+            Ok(exp as u128)
+        }
+    }
+
+    /// Returns how far num lies above the previous multiple of align, which must be positive.
+    fn alignment_rem(word: Word, name: &str, num: i128, align: i128) -> Result<i128, CorkError> {
+        if word.cmp(align, 0).is_le() {
+            return Err(CorkError::Eval(format!(
+                "{} needs a positive alignment",
+                name
+            )));
+        }
+        if word.signed() {
+            Ok(num.rem_euclid(align))
+        } else {
+            Ok(((num as u128) % (align as u128)) as i128)
+        }
+    }
+
+    /// Swaps the bytes of the low bits of num, leaving the bits above them clear.
+    fn swap_low_bytes(
+        name: &str,
+        word: Word,
+        num: i128,
+        bits: u32,
+    ) -> Result<Option<i128>, CorkError> {
+        if word.bits() < bits {
+            return Err(CorkError::Eval(format!(
+                "{} needs a word of at least {} bits",
+                name, bits
+            )));
+        }
+        let low = word.bits_of(num) & (u128::MAX >> (128 - bits));
+        Ok(Some(word.value_of(low.swap_bytes() >> (128 - bits))))
+    }
+
+    /// Rotates num left by amount within the word. A negative amount rotates right.
+    fn rotate_left(word: Word, num: i128, amount: i128) -> i128 {
+        let amount = amount.rem_euclid(word.bits() as i128) as u32;
+        let bits = word.bits_of(num);
+        if amount == 0 {
+            return word.value_of(bits);
+        }
+        word.value_of((bits << amount) | (bits >> (word.bits() - amount)))
+    }
+
+    /// Rotates num right by amount within the word. A negative amount rotates left.
+    fn rotate_right(word: Word, num: i128, amount: i128) -> i128 {
+        let bits = word.bits() as i128;
+        rotate_left(word, num, bits - amount.rem_euclid(bits))
+    }
+
+    /// Checks that hi and lo are bits of the word, with hi not below lo.
+    fn bit_range(word: Word, hi: i128, lo: i128) -> Result<(u32, u32), CorkError> {
+        let bit_index = |index: i128| {
+            if (0..word.bits() as i128).contains(&index) {
+                Ok(index as u32)
+            } else {
+                Err(CorkError::BitOutOfRange {
+                    index: word.decimal(index),
+                    word,
+                })
+            }
+        };
+        let (hi, lo) = (bit_index(hi)?, bit_index(lo)?);
+        if hi < lo {
+            return Err(CorkError::Eval(format!(
+                "high bit {} is below low bit {}",
+                hi, lo
+            )));
+        }
+        Ok((hi, lo))
+    }
+
+    fn field_mask(hi: u32, lo: u32) -> u128 {
+        (u128::MAX >> (127 - (hi - lo))) << lo
+    }
+
+    /// Returns the bits hi to lo of num, shifted down to bit 0.
+    fn extract_bits(word: Word, num: i128, hi: u32, lo: u32) -> i128 {
+        word.value_of((word.bits_of(num) & field_mask(hi, lo)) >> lo)
+    }
+
+    /// Replaces the bits hi to lo of num with the low bits of value.
+    fn insert_bits(word: Word, num: i128, hi: u32, lo: u32, value: i128) -> i128 {
+        let mask = field_mask(hi, lo);
+        let bits = (word.bits_of(num) & !mask) | (((value as u128) << lo) & mask);
+        word.value_of(bits)
+    }
+
+    fn eval_bit_range(range: &BitRange, env: &Env, scope: &Scope) -> Result<(u32, u32), CorkError> {
+        let hi = eval_in_scope(&range.hi, env, scope)?;
+        let lo = match &range.lo {
+            Some(lo) => eval_in_scope(lo, env, scope)?,
+            None => hi,
+        };
+        bit_range(env.word(), hi, lo)
+    }
+
+    /// Evaluates assignment, returning the new value of its variable.
+    pub fn eval_assignment(assignment: &Assignment, env: &Env) -> Result<Value, CorkError> {
+        unit_dim(&assignment.expr, env)?;
+        let Some(range) = &assignment.range else {
+            return eval_expr(&assignment.expr, env);
+        };
+        let Some(old) = env.var(&assignment.name) else {
+            return Err(CorkError::Eval(format!(
+                "{} is not defined",
+                assignment.name
+            )));
+        };
+        #[cfg(feature = "bignum")]
+        if env.big() {
+            return big::eval_slice_assignment(old, range, &assignment.expr, env).map(Value::Big);
+        }
+        let word = env.word();
+        let value = eval_in_scope(&assignment.expr, env, &Scope::default())?;
+        let (hi, lo) = eval_bit_range(range, env, &Scope::default())?;
+        Ok(Value::Fixed(insert_bits(
+            word,
+            old.to_fixed(word),
+            hi,
+            lo,
+            value,
+        )))
+    }
+
+    /// A Scope holds the arguments of the function call being evaluated and their dimensions,
+    /// along with how deeply nested that call is.
+    #[derive(Default)]
+    struct Scope<'a, T = i128> {
+        args: HashMap<&'a str, T>,
+        dims: HashMap<&'a str, Dim>,
+        depth: usize,
+    }
+
+    impl<T> Scope<'_, T> {
+        /// Returns the dimension of expr, which is evaluated in the scope.
+        fn dim(&self, expr: &Expr, env: &Env) -> Result<Dim, CorkError> {
+            let scope = DimScope {
+                args: self.dims.clone(),
+                calls: Vec::new(),
+            };
+            dim_in_scope(expr, env, &scope)
+        }
+    }
+
+    /// A DimScope holds the dimensions of the arguments of the function call being checked,
+    /// along with the functions whose bodies are being checked. The recursive calls of these
+    /// functions are of unknown dimension, so that checking them terminates.
+    #[derive(Default)]
+    struct DimScope<'a> {
+        args: HashMap<&'a str, Dim>,
+        calls: Vec<&'a str>,
+    }
+
+    /// eval_expr evaluates expr in env. Every intermediate result is truncated
+    /// to the word, and overflows are handled as per the overflow policy.
+    /// If the width is big, numbers are never truncated instead.
+    pub fn eval_expr(expr: &Expr, env: &Env) -> Result<Value, CorkError> {
+        unit_dim(expr, env)?;
+        #[cfg(feature = "bignum")]
+        if env.big() {
+            return big::eval_in_scope(expr, env, &Scope::default()).map(Value::Big);
+        }
+        eval_in_scope(expr, env, &Scope::default()).map(Value::Fixed)
+    }
+
+    /// unit_dim checks that the units of expr agree, and returns the dimension of its value.
+    /// Variables and ans have the dimension of the value they were given, and the body of a
+    /// function is checked with the dimensions of the arguments of each call.
+    pub fn unit_dim(expr: &Expr, env: &Env) -> Result<Dim, CorkError> {
+        dim_in_scope(expr, env, &DimScope::default())
+    }
+
+    fn dim_in_scope<'a>(
+        expr: &'a Expr,
+        env: &'a Env,
+        scope: &DimScope<'a>,
+    ) -> Result<Dim, CorkError> {
+        let dim_of = |expr| dim_in_scope(expr, env, scope);
+        let mismatch = |op: &dyn ToString, dims: &[Dim]| CorkError::UnitMismatch {
+            op: op.to_string(),
+            dims: dims.to_vec(),
+        };
+        match expr {
+            Expr::Quantity(quantity) => Ok(quantity.unit.dim()),
+            Expr::Literal(_) | Expr::Size(_) => Ok(Dim::Count),
+            // a variable that is not defined is reported when evaluated
+            Expr::Var(name) => Ok(match scope.args.get(name.as_str()) {
+                Some(&dim) => dim,
+                None => env.var_dim(name).unwrap_or(Dim::Unknown),
+            }),
+            Expr::Ans => Ok(env.ans_dim()),
+            Expr::Call(call) => {
+                let args = call
+                    .args
+                    .iter()
+                    .map(dim_of)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let function = match env.functions.get(&call.name) {
+                    _ if builtin(&call.name).is_some() => return Ok(Dim::Count),
+                    Some(function) if function.params.len() == args.len() => function,
+                    // a call to a function that is not defined is reported when evaluated
+                    _ => return Ok(Dim::Unknown),
+                };
+                if scope.calls.contains(&call.name.as_str()) {
+                    return Ok(Dim::Unknown);
+                }
+                let mut calls = scope.calls.clone();
+                calls.push(&call.name);
+                let args = function.params.iter().map(String::as_str).zip(args);
+                let scope = DimScope {
+                    args: args.collect(),
+                    calls,
+                };
+                dim_in_scope(&function.body, env, &scope)
+            }
+            Expr::Slice(slice) => {
+                dim_of(&slice.expr)?;
+                Ok(Dim::Count)
+            }
+            Expr::UnOp(expr) => match (&expr.op, dim_of(&expr.expr)?) {
+                (UnOp::Neg, dim) => Ok(dim),
+                (UnOp::LogicalNot, _) | (UnOp::Not, Dim::Count | Dim::Unknown) => Ok(Dim::Count),
+                (op, dim) => Err(mismatch(op, &[dim])),
+            },
+            Expr::BinOp(expr) => {
+                let (left, right) = (dim_of(&expr.left)?, dim_of(&expr.right)?);
+                let dim = match (&expr.op, left, right) {
+                    (Op::Mul | Op::Div | Op::Rem | Op::LShift | Op::RShift, _, Dim::Count) => {
+                        Some(left)
+                    }
+                    (Op::Mul, Dim::Count | Dim::Unknown, _) => Some(right),
+                    (Op::Mul, _, Dim::Unknown) => Some(left),
+                    (Op::Div, Dim::Unknown, _) | (Op::Div, _, Dim::Unknown) => Some(Dim::Unknown),
+                    (Op::Div, _, _) if left == right => Some(Dim::Count),
+                    (Op::LShift | Op::RShift, _, Dim::Unknown) => Some(left),
+                    (Op::Add | Op::Sub | Op::Rem, _, _) => left.common(right),
+                    (Op::Eq | Op::Ne | Op::Lt | Op::Le | Op::Gt | Op::Ge, _, _) => {
+                        left.common(right).map(|_| Dim::Count)
+                    }
+                    (Op::At, Dim::Unknown, _) | (Op::At, _, Dim::Unknown) => Some(Dim::Unknown),
+                    (Op::At, Dim::Time, Dim::Freq) | (Op::At, Dim::Freq, Dim::Time) => {
+                        Some(Dim::Count)
+                    }
+                    (Op::At, Dim::Count, Dim::Time) | (Op::At, Dim::Time, Dim::Count) => {
+                        Some(Dim::Freq)
+                    }
+                    (Op::At, Dim::Count, Dim::Freq) | (Op::At, Dim::Freq, Dim::Count) => {
+                        Some(Dim::Time)
+                    }
+                    (Op::At, _, _) => None,
+                    // the bitwise and logical operators are for plain numbers only
+                    (_, Dim::Count | Dim::Unknown, Dim::Count | Dim::Unknown) => Some(Dim::Count),
+                    _ => None,
+                };
+                dim.ok_or_else(|| mismatch(&expr.op, &[left, right]))
+            }
+            Expr::Cond(cond) => {
+                dim_of(&cond.cond)?;
+                let (if_true, if_false) = (dim_of(&cond.if_true)?, dim_of(&cond.if_false)?);
+                if_true
+                    .common(if_false)
+                    .ok_or_else(|| mismatch(&"?:", &[if_true, if_false]))
+            }
+        }
+    }
+
+    /// The number of nanoseconds in a second.
+    const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+    /// Arranges the operands of "left @ right" as a * b / c. A time at a frequency is the
+    /// number of ticks in the time, and a number of ticks at a frequency is the time they
+    /// take, as a number of ticks in a time is their frequency.
+    fn at_terms<T: From<u32>>(dims: (Dim, Dim), left: T, right: T) -> Result<(T, T, T), CorkError> {
+        match dims {
+            (Dim::Time, Dim::Freq) | (Dim::Freq, Dim::Time) => {
+                Ok((left, right, NANOS_PER_SEC.into()))
+            }
+            (Dim::Count, Dim::Time | Dim::Freq) => Ok((left, NANOS_PER_SEC.into(), right)),
+            (Dim::Time | Dim::Freq, Dim::Count) => Ok((right, NANOS_PER_SEC.into(), left)),
+            (left, right) => Err(CorkError::UnitMismatch {
+                op: Op::At.to_string(),
+                dims: vec![left, right],
+            }),
+        }
+    }
+
+    /// Computes a * b / c, rounded toward zero, with a product as wide as an i128, so that only
+    /// the quotient has to fit in the word.
+    fn mul_div(word: Word, (a, b, c): (i128, i128, i128), overflow: Overflow) -> Option<i128> {
+        let quotient = a.checked_mul(b)? / c;
+        // adding 0 fits the quotient into the word, following the overflow policy
+        word.add(quotient, 0, overflow)
+    }
+
+    fn eval_in_scope(expr: &Expr, env: &Env, scope: &Scope) -> Result<i128, CorkError> {
+        let word = env.word();
+        let overflow = env.overflow();
+        match &expr {
+            Expr::Literal(literal) => fit_literal(literal, word, overflow),
+            Expr::UnOp(expr) => {
+                // the smallest number of a signed word is written as a negated literal, such as
+                // -128, although its magnitude is one more than the largest number
+                if let (UnOp::Neg, Expr::Literal(literal)) = (&expr.op, expr.expr.as_ref()) {
+                    if word.signed()
+                        && literal_bits(&literal.value) == Some(word.min().unsigned_abs())
+                    {
+                        return Ok(word.min());
+                    }
+                }
+                let operand = eval_in_scope(expr.expr.as_ref(), env, scope)?;
+                match expr.op {
+                    // negation is subtraction from 0, so it overflows just the same
+                    UnOp::Neg => word
+                        .sub(0, operand, overflow)
+                        .ok_or_else(|| overflow_error(&expr.op, &[operand], word)),
+                    UnOp::Not => Ok(word.wrap(!operand)),
+                    UnOp::LogicalNot => Ok((operand == 0) as i128),
+                }
+            }
+            Expr::BinOp(expr) => {
+                let left = eval_in_scope(expr.left.as_ref(), env, scope)?;
+                // the logical operators short-circuit, so their right side may not be evaluated
+                match (&expr.op, left != 0) {
+                    (Op::LogicalAnd, false) => return Ok(0),
+                    (Op::LogicalOr, true) => return Ok(1),
+                    _ => {}
+                }
+                let right = eval_in_scope(expr.right.as_ref(), env, scope)?;
+                let result = match expr.op {
+                    // note that order does not matter here
+                    Op::Add => word.add(left, right, overflow),
+                    Op::Sub => word.sub(left, right, overflow),
+                    Op::Mul => word.mul(left, right, overflow),
+                    Op::And => Some(left & right),
+                    Op::Xor => Some(left ^ right),
+                    Op::Or => Some(left | right),
+                    Op::LShift => word.shl(left, shift_amount(right, word)?, overflow),
+                    Op::RShift => word.shr(left, shift_amount(right, word)?, overflow),
+                    Op::Div => {
+                        if right == 0 {
+                            return Err(CorkError::Eval(String::from("Cannot divide by 0")));
+                        }
+                        word.div(left, right, overflow)
+                    }
+                    Op::Rem => {
+                        if right == 0 {
+                            return Err(CorkError::Eval(String::from("Cannot divide by 0")));
+                        }
+                        Some(word.rem(left, right))
+                    }
+                    Op::Eq => Some((left == right) as i128),
+                    Op::Ne => Some((left != right) as i128),
+                    Op::Lt => Some(word.cmp(left, right).is_lt() as i128),
+                    Op::Le => Some(word.cmp(left, right).is_le() as i128),
+                    Op::Gt => Some(word.cmp(left, right).is_gt() as i128),
+                    Op::Ge => Some(word.cmp(left, right).is_ge() as i128),
+                    Op::LogicalAnd | Op::LogicalOr => Some((right != 0) as i128),
+                    Op::Pow => word.pow(left, exponent(word, right)?, overflow),
+                    Op::At => {
+                        let dims = (scope.dim(&expr.left, env)?, scope.dim(&expr.right, env)?);
+                        let terms = at_terms(dims, left, right)?;
+                        if terms.2 == 0 {
+                            return Err(CorkError::Eval(String::from("Cannot divide by 0")));
+                        }
+                        mul_div(word, terms, overflow)
+                    }
+                };
+                result.ok_or_else(|| overflow_error(&expr.op, &[left, right], word))
+            }
+            Expr::Var(name) => match scope.args.get(name.as_str()) {
+                Some(value) => Ok(word.wrap(*value)),
+                None => match env.var(name) {
+                    Some(value) => Ok(value.to_fixed(word)),
+                    None => Err(CorkError::Eval(format!("{} is not defined", name))),
+                },
+            },
+            Expr::Call(call) => eval_call(call, env, scope),
+            Expr::Slice(slice) => {
+                let num = eval_in_scope(&slice.expr, env, scope)?;
+                let (hi, lo) = eval_bit_range(&slice.range, env, scope)?;
+                Ok(extract_bits(word, num, hi, lo))
+            }
+            Expr::Cond(cond) => {
+                if eval_in_scope(&cond.cond, env, scope)? != 0 {
+                    eval_in_scope(&cond.if_true, env, scope)
+                } else {
+                    eval_in_scope(&cond.if_false, env, scope)
+                }
+            }
+            Expr::Size(size) => {
+                let multiple = size.multiple(env.size_prefixes());
+                word.fit_unsigned(size.num as u128 * multiple, overflow)
+                    .ok_or_else(|| {
+                        overflow_error(&Op::Mul, &[size.num as i128, multiple as i128], word)
+                    })
+            }
+            Expr::Quantity(quantity) => {
+                let scale = quantity.unit.scale();
+                word.fit_unsigned(quantity.num as u128 * scale, overflow)
+                    .ok_or_else(|| {
+                        overflow_error(&Op::Mul, &[quantity.num as i128, scale as i128], word)
+                    })
+            }
+            Expr::Ans => Ok(env.ans().to_fixed(word)),
+        }
+    }
+
+    fn eval_call(call: &CallExpr, env: &Env, scope: &Scope) -> Result<i128, CorkError> {
+        if let Some(builtin) = builtin(&call.name) {
+            check_arity(&call.name, builtin.arity, call.args.len())?;
+            let args = call
+                .args
+                .iter()
+                .map(|arg| eval_in_scope(arg, env, scope))
+                .collect::<Result<Vec<_>, _>>()?;
+            return (builtin.eval)(&args, env)?
+                .ok_or_else(|| overflow_error(&call.name, &args, env.word()));
+        }
+
+        let function = user_function(call, env, scope.depth)?;
+        let mut args = HashMap::new();
+        let mut dims = HashMap::new();
+        for (param, arg) in function.params.iter().zip(&call.args) {
+            args.insert(param.as_str(), eval_in_scope(arg, env, scope)?);
+            dims.insert(param.as_str(), scope.dim(arg, env)?);
+        }
+        let scope = Scope {
+            args,
+            dims,
+            depth: scope.depth + 1,
+        };
+        // the spans of the body are of the line that defined the function, not of this one
+        eval_in_scope(&function.body, env, &scope).map_err(|err| match err.span() {
+            Some(_) => CorkError::Eval(format!("{}, in the body of {}", err, call.name)),
+            None => err,
+        })
+    }
+
+    /// Converts the number that literal is into the word. A literal that does not fit is
+    /// saturated if the overflow policy is saturating, and is an error if it is checked.
+    /// If it is wrapping, the literal is read as the bits of the word, so 0xff is -1 in a
+    /// signed 8-bit word, and it is only an error for the literal to have more bits.
+    fn fit_literal(
+        literal: &LiteralExpr,
+        word: Word,
+        overflow: Overflow,
+    ) -> Result<i128, CorkError> {
+        let span = literal.span.clone();
+        match literal_bits(&literal.value) {
+            Some(bits) if overflow != Overflow::Wrapping || word.bits_of(bits as i128) == bits => {
+                word.fit_unsigned(bits, overflow)
+                    .ok_or(CorkError::LiteralOverflow { span, word })
+            }
+            _ => match overflow {
+                Overflow::Checked => Err(CorkError::LiteralOverflow { span, word }),
+                Overflow::Wrapping => Err(CorkError::LiteralOutOfRange {
+                    span,
+                    max_bits: word.bits().into(),
+                }),
+                Overflow::Saturating => Ok(word.max()),
+            },
+        }
+    }
+
+    /// Returns the number that the value of a literal is, or None if it is too large for a u128.
+    fn literal_bits(value: &Value) -> Option<u128> {
+        match value {
+            Value::Fixed(num) => Some(*num as u128),
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => u128::try_from(num).ok(),
+        }
+    }
+
+    /// Looks up the user-defined function that call refers to, checking that it is
+    /// given the right number of arguments and that it is not nested too deeply.
+    fn user_function<'a>(
+        call: &CallExpr,
+        env: &'a Env,
+        depth: usize,
+    ) -> Result<&'a Function, CorkError> {
+        let Some(function) = env.functions.get(&call.name) else {
+            return Err(CorkError::Eval(format!("{} is not a function", call.name)));
+        };
+        check_arity(&call.name, function.params.len(), call.args.len())?;
+        if depth >= MAX_CALL_DEPTH {
+            return Err(CorkError::Eval(format!(
+                "{} exceeded the maximum call depth of {}",
+                call.name, MAX_CALL_DEPTH
+            )));
+        }
+        Ok(function)
+    }
+
+    fn check_arity(name: &str, arity: usize, given: usize) -> Result<(), CorkError> {
+        if arity == given {
+            Ok(())
+        } else {
+            Err(CorkError::Eval(format!(
+                "{} takes {} argument(s) but {} were given",
+                name, arity, given
+            )))
+        }
+    }
+
+    fn overflow_error(op: &impl ToString, operands: &[i128], word: Word) -> CorkError {
+        CorkError::Overflow {
+            op: op.to_string(),
+            operands: operands.iter().map(|num| word.decimal(*num)).collect(),
+            word,
+        }
+    }
+
+    /// Returns the amount to shift by, read as signed or unsigned as per the word. In an
+    /// unsigned 128-bit word, an amount with the top bit set is too large rather than negative.
+    fn shift_amount(amount: i128, word: Word) -> Result<u32, CorkError> {
+        if !word.signed() {
+            Ok(u32::try_from(amount as u128).unwrap_or(u32::MAX))
+        } else if amount < 0 {
+            Err(CorkError::Eval(String::from(
+                "Cannot shift by a negative amount",
+            )))
+        } else {
+            Ok(u32::try_from(amount).unwrap_or(u32::MAX))
+        }
+    }
+
+    /// Evaluation with big numbers, which are never truncated and so never overflow.
+    /// The size of a result is still limited, so that a typo cannot exhaust the memory.
+    #[cfg(feature = "bignum")]
+    mod big {
+        use super::*;
+        use num_bigint::BigInt;
+        use num_traits::{One, Signed, ToPrimitive, Zero};
+
+        /// A BigBuiltin is a built-in function that is defined for big numbers.
+        struct BigBuiltin {
+            name: &'static str,
+            arity: usize,
+            eval: fn(&[BigInt]) -> Result<BigInt, CorkError>,
+        }
+
+        /// The built-in functions for big numbers. Those that depend on the width of
+        /// the word are left out, as big numbers have no width.
+        static BUILTINS: &[BigBuiltin] = &[
+            BigBuiltin {
+                name: "pow",
+                arity: 2,
+                eval: |args| pow(&args[0], &args[1]),
+            },
+            BigBuiltin {
+                name: "gcd",
+                arity: 2,
+                eval: |args| Ok(gcd(&args[0], &args[1])),
+            },
+            BigBuiltin {
+                name: "lcm",
+                arity: 2,
+                eval: |args| {
+                    if args[0].is_zero() || args[1].is_zero() {
+                        return Ok(BigInt::zero());
+                    }
+                    let lcm = (&args[0] / gcd(&args[0], &args[1]) * &args[1]).abs();
+                    limit("lcm", lcm)
+                },
+            },
+            BigBuiltin {
+                name: "isqrt",
+                arity: 1,
+                eval: |args| {
+                    if args[0].is_negative() {
+                        return Err(CorkError::Eval(String::from(
+                            "isqrt is not defined for negative numbers",
+                        )));
+                    }
+                    Ok(args[0].sqrt())
+                },
+            },
+            BigBuiltin {
+                name: "log2",
+                arity: 1,
+                eval: |args| {
+                    if !args[0].is_positive() {
+                        return Err(CorkError::Eval(String::from(
+                            "log2 is only defined for positive numbers",
+                        )));
+                    }
+                    Ok(BigInt::from(args[0].bits() - 1))
+                },
+            },
+            BigBuiltin {
+                name: "align_up",
+                arity: 2,
+                eval: |args| {
+                    let rem = alignment_rem("align_up", &args[0], &args[1])?;
+                    if rem.is_zero() {
+                        return Ok(args[0].clone());
+                    }
+                    limit("align_up", &args[0] + &args[1] - rem)
+                },
+            },
+            BigBuiltin {
+                name: "align_down",
+                arity: 2,
+                eval: |args| Ok(&args[0] - alignment_rem("align_down", &args[0], &args[1])?),
+            },
+        ];
+
+        fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+            let (mut a, mut b) = (a.abs(), b.abs());
+            while !b.is_zero() {
+                let rem = &a % &b;
+                (a, b) = (b, rem);
+            }
+            a
+        }
+
+        fn alignment_rem(name: &str, num: &BigInt, align: &BigInt) -> Result<BigInt, CorkError> {
+            if !align.is_positive() {
+                return Err(CorkError::Eval(format!(
+                    "{} needs a positive alignment",
+                    name
+                )));
+            }
+            Ok(((num % align) + align) % align)
+        }
+
+        fn pow(base: &BigInt, exp: &BigInt) -> Result<BigInt, CorkError> {
+            if exp.is_negative() {
+                return Err(CorkError::Eval(String::from(
+                    "Cannot raise to a negative power",
+                )));
+            }
+            // 0, 1 and -1 stay small whatever the exponent is
+            if base.abs() <= BigInt::one() {
+                let odd = exp % 2 == BigInt::one();
+                return Ok(if base.is_negative() && !odd {
+                    BigInt::one()
+                } else {
+                    base.clone()
+                });
+            }
+            match exp.to_u64() {
+                // the result has at least (bits - 1) * exp + 1 bits
+                Some(exp) if (base.bits() - 1).saturating_mul(exp) < MAX_BIG_BITS => {
+                    limit("**", base.pow(exp as u32))
+                }
+                _ => Err(too_large("**")),
+            }
+        }
+
+        /// Checks that num is no larger than MAX_BIG_BITS.
+        fn limit(op: impl fmt::Display, num: BigInt) -> Result<BigInt, CorkError> {
+            if num.bits() > MAX_BIG_BITS {
+                Err(too_large(op))
+            } else {
+                Ok(num)
+            }
+        }
+
+        fn too_large(op: impl fmt::Display) -> CorkError {
+            CorkError::Eval(format!(
+                "The result of {} does not fit in {} bits",
+                op, MAX_BIG_BITS
+            ))
+        }
+
+        /// Converts amount into a number of bits, which is capped at just above MAX_BIG_BITS.
+        fn bit_count(amount: &BigInt) -> u64 {
+            amount.to_u64().unwrap_or(u64::MAX).min(MAX_BIG_BITS + 1)
+        }
+
+        fn field_mask(hi: u64, lo: u64) -> BigInt {
+            ((BigInt::one() << (hi - lo + 1)) - 1) << lo
+        }
+
+        fn eval_bit_range(
+            range: &BitRange,
+            env: &Env,
+            scope: &Scope<BigInt>,
+        ) -> Result<(u64, u64), CorkError> {
+            let hi = eval_in_scope(&range.hi, env, scope)?;
+            let lo = match &range.lo {
+                Some(lo) => eval_in_scope(lo, env, scope)?,
+                None => hi.clone(),
+            };
+            let bit_index = |index: BigInt| {
+                if index.is_negative() || bit_count(&index) >= MAX_BIG_BITS {
+                    Err(CorkError::Eval(format!(
+                        "bit {} is out of range for big numbers",
+                        index
+                    )))
+                } else {
+                    Ok(bit_count(&index))
+                }
+            };
+            let (hi, lo) = (bit_index(hi)?, bit_index(lo)?);
+            if hi < lo {
+                return Err(CorkError::Eval(format!(
+                    "high bit {} is below low bit {}",
+                    hi, lo
+                )));
+            }
+            Ok((hi, lo))
+        }
+
+        /// Evaluates the assignment of expr to a range of bits of the variable old.
+        pub fn eval_slice_assignment(
+            old: &Value,
+            range: &BitRange,
+            expr: &Expr,
+            env: &Env,
+        ) -> Result<BigInt, CorkError> {
+            let value = eval_in_scope(expr, env, &Scope::default())?;
+            let (hi, lo) = eval_bit_range(range, env, &Scope::default())?;
+            let mask = field_mask(hi, lo);
+            Ok((old.to_big(env.word()) & !&mask) | ((value << lo) & mask))
+        }
+
+        pub fn eval_in_scope(
+            expr: &Expr,
+            env: &Env,
+            scope: &Scope<BigInt>,
+        ) -> Result<BigInt, CorkError> {
+            let word = env.word();
+            match &expr {
+                // a fixed literal is never negative, as it is fixed only if it fits an i128
+                Expr::Literal(literal) => Ok(match &literal.value {
+                    Value::Fixed(num) => BigInt::from(*num),
+                    Value::Big(num) => num.clone(),
+                }),
+                Expr::UnOp(expr) => {
+                    let operand = eval_in_scope(expr.expr.as_ref(), env, scope)?;
+                    Ok(match expr.op {
+                        UnOp::Neg => -operand,
+                        UnOp::Not => !operand,
+                        UnOp::LogicalNot => BigInt::from(operand.is_zero() as u8),
+                    })
+                }
+                Expr::BinOp(expr) => {
+                    let left = eval_in_scope(expr.left.as_ref(), env, scope)?;
+                    match (&expr.op, left.is_zero()) {
+                        (Op::LogicalAnd, true) => return Ok(BigInt::zero()),
+                        (Op::LogicalOr, false) => return Ok(BigInt::one()),
+                        _ => {}
+                    }
+                    let right = eval_in_scope(expr.right.as_ref(), env, scope)?;
+                    let truth = |value: bool| BigInt::from(value as u8);
+                    let result = match expr.op {
+                        Op::Add => left + right,
+                        Op::Sub => left - right,
+                        Op::Mul => left * right,
+                        Op::And => left & right,
+                        Op::Xor => left ^ right,
+                        Op::Or => left | right,
+                        Op::LShift | Op::RShift if right.is_negative() => {
+                            return Err(CorkError::Eval(String::from(
+                                "Cannot shift by a negative amount",
+                            )))
+                        }
+                        Op::LShift if left.is_zero() => left,
+                        Op::LShift => {
+                            let amount = bit_count(&right);
+                            if left.bits() + amount > MAX_BIG_BITS {
+                                return Err(too_large(&expr.op));
+                            }
+                            left << amount
+                        }
+                        Op::RShift => left >> bit_count(&right),
+                        Op::Div | Op::Rem if right.is_zero() => {
+                            return Err(CorkError::Eval(String::from("Cannot divide by 0")))
+                        }
+                        Op::Div => left / right,
+                        Op::Rem => left % right,
+                        Op::Eq => truth(left == right),
+                        Op::Ne => truth(left != right),
+                        Op::Lt => truth(left < right),
+                        Op::Le => truth(left <= right),
+                        Op::Gt => truth(left > right),
+                        Op::Ge => truth(left >= right),
+                        Op::LogicalAnd | Op::LogicalOr => truth(!right.is_zero()),
+                        Op::Pow => pow(&left, &right)?,
+                        Op::At => {
+                            let dims = (scope.dim(&expr.left, env)?, scope.dim(&expr.right, env)?);
+                            let (a, b, c) = at_terms(dims, left, right)?;
+                            if c.is_zero() {
+                                return Err(CorkError::Eval(String::from("Cannot divide by 0")));
+                            }
+                            a * b / c
+                        }
+                    };
+                    limit(&expr.op, result)
+                }
+                Expr::Var(name) => match scope.args.get(name.as_str()) {
+                    Some(value) => Ok(value.clone()),
+                    None => match env.var(name) {
+                        Some(value) => Ok(value.to_big(word)),
+                        None => Err(CorkError::Eval(format!("{} is not defined", name))),
+                    },
+                },
+                Expr::Call(call) => eval_call(call, env, scope),
+                Expr::Slice(slice) => {
+                    let num = eval_in_scope(&slice.expr, env, scope)?;
+                    let (hi, lo) = eval_bit_range(&slice.range, env, scope)?;
+                    Ok((num & field_mask(hi, lo)) >> lo)
+                }
+                Expr::Cond(cond) => {
+                    if eval_in_scope(&cond.cond, env, scope)?.is_zero() {
+                        eval_in_scope(&cond.if_false, env, scope)
+                    } else {
+                        eval_in_scope(&cond.if_true, env, scope)
+                    }
+                }
+                Expr::Size(size) => Ok(BigInt::from(size.num) * size.multiple(env.size_prefixes())),
+                Expr::Quantity(quantity) => {
+                    Ok(BigInt::from(quantity.num as u128 * quantity.unit.scale()))
+                }
+                Expr::Ans => Ok(env.ans().to_big(word)),
+            }
+        }
+
+        fn eval_call(
+            call: &CallExpr,
+            env: &Env,
+            scope: &Scope<BigInt>,
+        ) -> Result<BigInt, CorkError> {
+            if let Some(builtin) = BUILTINS.iter().find(|builtin| builtin.name == call.name) {
+                check_arity(&call.name, builtin.arity, call.args.len())?;
+                let args = call
+                    .args
+                    .iter()
+                    .map(|arg| eval_in_scope(arg, env, scope))
+                    .collect::<Result<Vec<_>, _>>()?;
+                return (builtin.eval)(&args);
+            }
+            if builtin(&call.name).is_some() {
+                return Err(CorkError::Eval(format!(
+                    "{} needs a word of fixed width",
+                    call.name
+                )));
+            }
+
+            let function = user_function(call, env, scope.depth)?;
+            let mut args = HashMap::new();
+            let mut dims = HashMap::new();
+            for (param, arg) in function.params.iter().zip(&call.args) {
+                args.insert(param.as_str(), eval_in_scope(arg, env, scope)?);
+                dims.insert(param.as_str(), scope.dim(arg, env)?);
+            }
+            let scope = Scope {
+                args,
+                dims,
+                depth: scope.depth + 1,
+            };
+            eval_in_scope(&function.body, env, &scope)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::eval::*;
+    use super::*;
+    use crate::format::{BitOrder, Endian};
+
+    /// Returns the literal of num, written at span.
+    fn literal_expr(num: i128, span: Range<usize>) -> Expr {
+        Expr::Literal(LiteralExpr {
+            value: Value::Fixed(num),
+            span,
+        })
+    }
+
+    /// Evaluates expr in env, which must not have a big width.
+    fn eval_fixed(expr: &Expr, env: &Env) -> Result<i128, CorkError> {
+        eval_expr(expr, env).map(|value| value.to_fixed(env.word()))
+    }
+
+    #[test]
+    fn test_expr_parse() {
+        let expr1 = Expr::BinOp(BinOpExpr {
+            left: Box::new(Expr::BinOp(BinOpExpr {
+                left: Box::new(literal_expr(5, 1..2)),
+                right: Box::new(literal_expr(6, 5..6)),
+                op: Op::Add,
+            })),
+            right: Box::new(literal_expr(2, 10..11)),
+            op: Op::Mul,
+        });
+        let expr_str1 = "(5 + 6) * 2";
+        assert_eq!(parse_line(expr_str1).unwrap(), Command::Expr(expr1));
+        let expr2 = Expr::BinOp(BinOpExpr {
+            right: Box::new(Expr::BinOp(BinOpExpr {
+                left: Box::new(literal_expr(5, 5..6)),
+                right: Box::new(literal_expr(6, 9..10)),
+                op: Op::Add,
+            })),
+            left: Box::new(literal_expr(2, 0..1)),
+            op: Op::Mul,
+        });
+        let expr_str2 = "2 * (5 + 6)";
+        assert_eq!(parse_line(expr_str2).unwrap(), Command::Expr(expr2));
+    }
+
+    #[test]
+    fn test_expr_eval() {
+        let expr1_str = "(5 + 6) * 2";
+        match parse_line(expr1_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 22),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr2_str = "2 * (5 + 6)";
+        match parse_line(expr2_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 22),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr3_str = "3 * (9 + 6) - 4";
+        match parse_line(expr3_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 41),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr4_str = "6-57*(18+4/73)+38 *  124";
+        match parse_line(expr4_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 3692)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr5_str = "2 + (((7 * 2) - 4) / 2) + 8 * 9 / 4";
+        match parse_line(expr5_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 25),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr6_str = "(3 + 2) - 1 / 1 * 3 + 5 * 4 / 10 - 1";
+        match parse_line(expr6_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 3),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr7_str = "8 / 2 * 3 - 9 - 6 * (15 / 3 / 5)";
+        match parse_line(expr7_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), -3),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr8_str = "24 / (2 * (12 / 4)) - ((8 * 3) / 6)";
+        match parse_line(expr8_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 0),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr9_str = "3 * 512 >> 4 - 2";
+        match parse_line(expr9_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 384)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let expr10_str = "3 * (512 >> 4) - 2";
+        match parse_line(expr10_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 94),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        // testing just the bitwise AND
+        let expr11_str = "0b0011 & 0b0110";
+        match parse_line(expr11_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 0b0010)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        }
+        // testing just the bitwise OR
+        let expr12_str = "0b0011 | 0b0110";
+        match parse_line(expr12_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 0b0111)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        }
+        // testing just the bitwise XOR
+        let expr13_str = "0b0011 ^ 0b0101";
+        match parse_line(expr13_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 0b0110)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        }
+        // testing all of the bitwise operators together
+        let expr14_str = "((0b0011 ^ 0b0101) & 0b0011) | 0b0111";
+        match parse_line(expr14_str).unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 0b0111)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        }
+        // mixing bitwise and "normal" operators
+        let expr15_str = "(((0b0011 ^ 0b0101) * 2) & 0b0101) + 1";
+        match parse_line(expr15_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 5),
+            _ => panic!("Should have parsed to an expr"),
+        }
+        // testing operator precedence / priority with bitwise ops
+        let expr16_str = "0b0100 ^ 0b0000 | 0b0101 * 2 & 0b0101 + 1";
+        match parse_line(expr16_str).unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_fixed(&expr, &Env::default()).unwrap(), 6),
+            _ => panic!("Should have parsed to an expr"),
+        }
+    }
+
+    #[test]
+    fn test_word_eval() {
+        let word = |bits: u32, signed| Word::new(bits.try_into().unwrap(), signed);
+        let cases = [
+            ("0xff + 1", word(8, false), 0),
+            ("0x7f + 1", word(8, true), -0x80),
+            ("0 - 1", word(16, false), 0xffff),
+            ("0x8000_0000 * 2", word(32, false), 0),
+            ("0xffff_ffff_ffff_ffff", word(64, true), -1),
+            (
+                "0xffff_ffff_ffff_ffff",
+                word(128, true),
+                0xffff_ffff_ffff_ffff,
+            ),
+            ("0xf0 >> 4", word(8, true), -1),
+            ("0xf0 >> 4", word(8, false), 0xf),
+            ("0xfe / 2", word(8, false), 0x7f),
+            ("0xfe / 2", word(8, true), -1),
+            ("1 << 64", word(64, false), 0),
+        ];
+        for (expr_str, word, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(
+                    eval_fixed(&expr, &Env::new(word, Overflow::Wrapping)).unwrap(),
+                    expected,
+                    "{} in {}",
+                    expr_str,
+                    word
+                ),
+                _ => panic!("Should have parsed to an expr"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unary_parse() {
+        let expr1 = Expr::BinOp(BinOpExpr {
+            left: Box::new(literal_expr(5, 0..1)),
+            right: Box::new(Expr::UnOp(UnOpExpr {
+                expr: Box::new(literal_expr(3, 5..6)),
+                op: UnOp::Neg,
+            })),
+            op: Op::Sub,
+        });
+        assert_eq!(parse_line("5 - -3").unwrap(), Command::Expr(expr1));
+        let expr2 = Expr::BinOp(BinOpExpr {
+            left: Box::new(Expr::UnOp(UnOpExpr {
+                expr: Box::new(Expr::UnOp(UnOpExpr {
+                    expr: Box::new(literal_expr(0xff, 2..6)),
+                    op: UnOp::Not,
+                })),
+                op: UnOp::LogicalNot,
+            })),
+            right: Box::new(literal_expr(2, 9..10)),
+            op: Op::Mul,
+        });
+        assert_eq!(parse_line("!~0xff * 2").unwrap(), Command::Expr(expr2));
+    }
+
+    #[test]
+    fn test_unary_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let eval = |expr_str, word, overflow| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(word, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+
+        assert_eq!(eval("-5", Word::default(), Overflow::Wrapping), Ok(-5));
+        assert_eq!(eval("-5", u8, Overflow::Wrapping), Ok(0xfb));
+        assert_eq!(eval("~0xf0", u8, Overflow::Wrapping), Ok(0x0f));
+        assert_eq!(eval("~0", i8, Overflow::Wrapping), Ok(-1));
+        assert_eq!(eval("!0", i8, Overflow::Wrapping), Ok(1));
+        assert_eq!(eval("!0x10", i8, Overflow::Wrapping), Ok(0));
+        assert_eq!(eval("-(2 + 3) * 4", i8, Overflow::Wrapping), Ok(-20));
+        assert_eq!(
+            eval("~0xfff & 0x1234", Word::default(), Overflow::Wrapping),
+            Ok(0x1000)
+        );
+        assert_eq!(eval("-0x80", i8, Overflow::Wrapping), Ok(-0x80));
+        assert_eq!(eval("-0x80", i8, Overflow::Saturating), Ok(-0x80));
+        assert_eq!(eval("-0x80", i8, Overflow::Checked), Ok(-0x80));
+        assert_eq!(eval("-(0x80)", i8, Overflow::Checked), Ok(-0x80));
+        assert_eq!(
+            eval("-0x81", i8, Overflow::Checked),
+            Err(CorkError::LiteralOverflow {
+                span: 1..5,
+                word: i8,
+            })
+        );
+        assert_eq!(
+            eval("-1", u8, Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("-"),
+                operands: vec![String::from("1")],
+                word: u8,
+            })
+        );
+    }
+
+    #[test]
+    fn test_overflow_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let eval = |expr_str, word, overflow| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(word, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+
+        assert_eq!(
+            eval("0x7f + 1", i8, Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("+"),
+                operands: vec![String::from("127"), String::from("1")],
+                word: i8,
+            })
+        );
+        assert_eq!(
+            eval("2 - 3", u8, Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("-"),
+                operands: vec![String::from("2"), String::from("3")],
+                word: u8,
+            })
+        );
+        assert_eq!(
+            eval("1 << 8", u8, Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("<<"),
+                operands: vec![String::from("1"), String::from("8")],
+                word: u8,
+            })
+        );
+        assert_eq!(eval("0x10 * 0x10", u8, Overflow::Wrapping), Ok(0));
+        assert_eq!(eval("0x10 * 0x10", u8, Overflow::Saturating), Ok(0xff));
+        assert_eq!(eval("0x40 * 4 - 1", i8, Overflow::Saturating), Ok(0x7e));
+        assert_eq!(eval("0x7f + 1 - 1", i8, Overflow::Wrapping), Ok(0x7f));
+        assert_eq!(eval("1 << 200", u8, Overflow::Saturating), Ok(0xff));
+        // in an unsigned 128-bit word, an amount with the top bit set is large, not negative
+        let u128 = Word::new(128.try_into().unwrap(), false);
+        assert_eq!(eval("1 << (1 << 127)", u128, Overflow::Wrapping), Ok(0));
+        assert_eq!(eval("-1 >> (1 << 127)", u128, Overflow::Wrapping), Ok(0));
+        assert!(matches!(
+            eval("1 << (1 << 127)", u128, Overflow::Checked),
+            Err(CorkError::Overflow { .. })
+        ));
+        assert_eq!(
+            eval(
+                "1 << (1 << 127)",
+                Word::new(128.try_into().unwrap(), true),
+                Overflow::Wrapping
+            ),
+            Err(CorkError::Eval(String::from(
+                "Cannot shift by a negative amount"
+            )))
+        );
+        assert_eq!(eval("0x7f + 0", i8, Overflow::Checked), Ok(0x7f));
+    }
+
+    #[test]
+    fn test_unit_eval() {
+        let i32 = Word::new(32.try_into().unwrap(), true);
+        let eval = |expr_str, word, overflow| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(word, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let cases = [
+            ("10ms", 10_000_000),
+            ("48MHz", 48_000_000),
+            ("1s + 500ms - 1'000us", 1_499_000_000),
+            ("10ms @ 48MHz", 480_000),
+            ("48MHz @ 10ms", 480_000),
+            ("480000 @ 48MHz", 10_000_000),
+            ("48MHz @ 480000", 10_000_000),
+            ("480000 @ 10ms", 48_000_000),
+            ("1 @ 3Hz", 333_333_333),
+            ("2 * 5ms / 1ms", 10),
+            ("-(1ms) % 300us", -100_000),
+            ("1us > 999ns", 1),
+            ("32kHz @ 1ms << 1", 64),
+            ("1 ? 2s : 1ns", 2_000_000_000),
+            ("popcnt(1ms)", 7),
+        ];
+        for (expr_str, expected) in cases {
+            assert_eq!(
+                eval(expr_str, Word::default(), Overflow::Checked),
+                Ok(expected),
+                "{}",
+                expr_str
+            );
+        }
+
+        let mismatch = |op: &str, dims: &[Dim]| {
+            Err(CorkError::UnitMismatch {
+                op: String::from(op),
+                dims: dims.to_vec(),
+            })
+        };
+        let errors = [
+            ("1s + 1Hz", mismatch("+", &[Dim::Time, Dim::Freq])),
+            ("1 + 1s", mismatch("+", &[Dim::Count, Dim::Time])),
+            ("1s * 1s", mismatch("*", &[Dim::Time, Dim::Time])),
+            ("1 / 1s", mismatch("/", &[Dim::Count, Dim::Time])),
+            ("1ms @ 1ms", mismatch("@", &[Dim::Time, Dim::Time])),
+            ("2 @ 3", mismatch("@", &[Dim::Count, Dim::Count])),
+            ("~1ms", mismatch("~", &[Dim::Time])),
+            ("1s & 1", mismatch("&", &[Dim::Time, Dim::Count])),
+            ("1 ? 1s : 1Hz", mismatch("?:", &[Dim::Time, Dim::Freq])),
+            (
+                "10 @ 0Hz",
+                Err(CorkError::Eval(String::from("Cannot divide by 0"))),
+            ),
+        ];
+        for (expr_str, error) in errors {
+            assert_eq!(
+                eval(expr_str, Word::default(), Overflow::Checked),
+                error,
+                "{}",
+                expr_str
+            );
+        }
+
+        // the ticks fit in the word, although their product with the frequency does not
+        assert_eq!(eval("10ms @ 48MHz", i32, Overflow::Checked), Ok(480_000));
+        assert_eq!(
+            eval("3s", i32, Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("*"),
+                operands: vec![String::from("3"), String::from("1000000000")],
+                word: i32,
+            })
+        );
+        assert_eq!(eval("3s", i32, Overflow::Saturating), Ok(i32::MAX as i128));
+        assert!(matches!(
+            eval(
+                "100 @ 1s",
+                Word::new(8.try_into().unwrap(), false),
+                Overflow::Checked
+            ),
+            Err(CorkError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unit_scope_eval() {
+        let mut env = Env::default();
+        for line in [
+            "t = 10ms",
+            "t2 = t",
+            "t2[0] = 1",
+            "fn ticks(t, f) = t @ f",
+            "fn fact(n) = n > 1 ? n * fact(n - 1) : 1",
+            "fn later(x) = x + 1ms",
+            "fn invert(x) = ~x",
+        ] {
+            match parse_line(line).unwrap() {
+                Command::Assign(assign) => {
+                    let (value, dim) = (assign.value(&env).unwrap(), assign.dim(&env).unwrap());
+                    env.set_var(assign.name(), value, dim);
+                }
+                Command::Define(function) => env.define(function).unwrap(),
+                _ => panic!("Should have parsed to an assignment or a function"),
+            }
+        }
+        env.set_ans(Value::Fixed(48_000_000), Dim::Freq);
+
+        let eval = |expr_str| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => {
+                unit_dim(&expr, &env).and_then(|dim| Ok((eval_fixed(&expr, &env)?, dim)))
+            }
+            _ => panic!("Should have parsed to an expr"),
+        };
+        let mismatch = |op: &str, dims: &[Dim]| CorkError::UnitMismatch {
+            op: String::from(op),
+            dims: dims.to_vec(),
+        };
+        let cases = [
+            ("t", Ok((10_000_000, Dim::Time))),
+            ("t @ 48MHz", Ok((480_000, Dim::Count))),
+            ("t2", Ok((10_000_001, Dim::Time))),
+            ("ans @ t", Ok((480_000, Dim::Count))),
+            ("ticks(10ms, 48MHz)", Ok((480_000, Dim::Count))),
+            ("ticks(480000, ans)", Ok((10_000_000, Dim::Time))),
+            ("ticks(t, 1kHz) * 1s", Ok((10_000_000_000, Dim::Time))),
+            ("fact(5) * 1ms", Ok((120_000_000, Dim::Time))),
+            ("later(t)", Ok((11_000_000, Dim::Time))),
+            ("t + 1Hz", Err(mismatch("+", &[Dim::Time, Dim::Freq]))),
+            ("t * ans", Err(mismatch("*", &[Dim::Time, Dim::Freq]))),
+            ("ticks(1s, 1s)", Err(mismatch("@", &[Dim::Time, Dim::Time]))),
+            ("later(1)", Err(mismatch("+", &[Dim::Count, Dim::Time]))),
+            ("invert(1ms)", Err(mismatch("~", &[Dim::Time]))),
+        ];
+        for (expr_str, expected) in cases {
+            assert_eq!(eval(expr_str), expected, "{}", expr_str);
+        }
+
+        match parse_line("fn bad(t) = t + 1s + 1Hz").unwrap() {
+            Command::Define(function) => {
+                assert_eq!(
+                    env.define(function),
+                    Err(mismatch("+", &[Dim::Time, Dim::Freq]))
+                )
+            }
+            _ => panic!("Should have parsed to a function"),
+        }
+        for (line, dim) in [
+            ("t to ms", Ok(Dim::Time)),
+            ("480000 to us", Ok(Dim::Time)),
+            ("ticks(t, ans) to ticks", Ok(Dim::Count)),
+            ("t to ticks", Err(mismatch("to ticks", &[Dim::Time]))),
+            ("ans to ms", Err(mismatch("to ms", &[Dim::Freq]))),
+        ] {
+            match parse_line(line).unwrap() {
+                Command::Convert(conversion) => assert_eq!(conversion.dim(&env), dim, "{}", line),
+                _ => panic!("Should have parsed to a conversion"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_unit_convert() {
+        let cases = [
+            ("10ms @ 48MHz to ticks", Ok(Value::Fixed(480_000))),
+            ("480000 @ 48MHz to us", Ok(Value::Fixed(10_000_000))),
+            ("ans to ms", Ok(Value::Fixed(0))),
+            ("5MHz to kHz", Ok(Value::Fixed(5_000_000))),
+            ("5 to Hz", Ok(Value::Fixed(5))),
+            (
+                "1s to ticks",
+                Err(CorkError::UnitMismatch {
+                    op: String::from("to ticks"),
+                    dims: vec![Dim::Time],
+                }),
+            ),
+            (
+                "48MHz to ms",
+                Err(CorkError::UnitMismatch {
+                    op: String::from("to ms"),
+                    dims: vec![Dim::Freq],
+                }),
+            ),
+        ];
+        for (line, value) in cases {
+            match parse_line(line).unwrap() {
+                Command::Convert(conversion) => {
+                    assert_eq!(conversion.value(&Env::default()), value, "{}", line)
+                }
+                _ => panic!("Should have parsed to a conversion"),
+            }
+        }
+        assert_eq!(
+            parse_line("1 to MHz"),
+            Ok(Command::Convert(ConvDirective {
+                expr: literal_expr(1, 0..1),
+                target: Target::Unit(Unit::MHz),
+            }))
+        );
+        for line in ["1 to mhz", "1 to ticks ms", "10 ms", "10mss", "1min"] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_assignment_parse() {
+        let assign1 = Assignment {
+            name: String::from("base"),
+            range: None,
+            expr: literal_expr(0x1000, 7..13),
+        };
+        assert_eq!(
+            parse_line("base = 0x1000").unwrap(),
+            Command::Assign(assign1)
+        );
+        let assign2 = Assignment {
+            name: String::from("_mask2"),
+            range: None,
+            expr: Expr::BinOp(BinOpExpr {
+                left: Box::new(Expr::Var(String::from("base"))),
+                right: Box::new(Expr::Ans),
+                op: Op::Or,
+            }),
+        };
+        assert_eq!(
+            parse_line("let _mask2 = base | ans").unwrap(),
+            Command::Assign(assign2)
+        );
+        assert_eq!(
+            parse_line("answer").unwrap(),
+            Command::Expr(Expr::Var(String::from("answer")))
+        );
+        assert_eq!(parse_line("vars").unwrap(), Command::Vars);
+        assert!(parse_line("ans = 5").is_err());
+        assert!(parse_line("to = 5").is_err());
+        assert!(parse_line("1x = 5").is_err());
+    }
+
+    #[test]
+    fn test_var_eval() {
+        let mut env = Env::default();
+        for (line, name) in [
+            ("base = 0xffff_8000_0000_0000", "base"),
+            ("off = 0x1000", "off"),
+        ] {
+            match parse_line(line).unwrap() {
+                Command::Assign(assign) => {
+                    assert_eq!(assign.name(), name);
+                    let value = assign.value(&env).unwrap();
+                    env.set_var(name, value, Dim::Count);
+                }
+                _ => panic!("Should have parsed to an assignment"),
+            }
+        }
+        match parse_line("base + off").unwrap() {
+            Command::Expr(expr) => {
+                assert_eq!(eval_fixed(&expr, &env).unwrap(), -0x7fff_ffff_f000)
+            }
+            _ => panic!("Should have parsed to an expr"),
+        }
+        match parse_line("base + undefined").unwrap() {
+            Command::Expr(expr) => assert_eq!(
+                eval_fixed(&expr, &env),
+                Err(CorkError::Eval(String::from("undefined is not defined")))
+            ),
+            _ => panic!("Should have parsed to an expr"),
+        }
+        assert_eq!(
+            env.vars().collect::<Vec<_>>(),
+            vec![
+                ("base", &Value::Fixed(-0x8000_0000_0000)),
+                ("off", &Value::Fixed(0x1000))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_parse() {
+        assert_eq!(
+            parse_line("layout PTE: present=0, pfn=12:51"),
+            Ok(Command::DefineLayout(LayoutDefinition {
+                name: String::from("PTE"),
+                layout: "present=0, pfn=12:51".parse().unwrap(),
+            }))
+        );
+        assert_eq!(
+            parse_line("pte + 1 as PTE"),
+            Ok(Command::Decode(DecodeDirective {
+                expr: Expr::BinOp(BinOpExpr {
+                    left: Box::new(Expr::Var(String::from("pte"))),
+                    right: Box::new(literal_expr(1, 6..7)),
+                    op: Op::Add,
+                }),
+                layout: String::from("PTE"),
+            }))
+        );
+        assert!(matches!(
+            parse_line("layout PTE: present=0, present=1"),
+            Err(CorkError::InvalidLayout(_))
+        ));
+        assert!(parse_line("layout PTE").is_err());
+        assert!(parse_line("5 as 6").is_err());
+        assert!(parse_line("as = 5").is_err());
+    }
+
+    #[test]
+    fn test_decode_eval() {
+        let mut env = Env::default();
+        env.define_layout("CR0", "PE=0, PG=31".parse().unwrap());
+        let Ok(Command::Decode(decode)) = parse_line("0x8000_0000 | 1 as CR0") else {
+            panic!("Should have parsed to a decode directive");
+        };
+        let value = decode.value(&env).unwrap();
+        let fields: Vec<_> = decode
+            .layout(&env)
+            .unwrap()
+            .decode(&value, env.word())
+            .unwrap()
+            .into_iter()
+            .map(|(field, value)| (field.to_string(), value))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (String::from("PE[0]"), Value::Fixed(1)),
+                (String::from("PG[31]"), Value::Fixed(1)),
+            ]
+        );
+
+        let Ok(Command::Decode(decode)) = parse_line("1 as EFER") else {
+            panic!("Should have parsed to a decode directive");
+        };
+        assert_eq!(
+            decode.layout(&env),
+            Err(CorkError::UnknownLayout(String::from("EFER")))
+        );
+    }
+
+    #[test]
+    fn test_function_parse() {
+        let function = Function {
+            name: String::from("page"),
+            params: vec![String::from("x")],
+            body: Expr::BinOp(BinOpExpr {
+                left: Box::new(Expr::Var(String::from("x"))),
+                right: Box::new(Expr::UnOp(UnOpExpr {
+                    expr: Box::new(literal_expr(0xfff, 18..23)),
+                    op: UnOp::Not,
+                })),
+                op: Op::And,
+            }),
+        };
+        assert_eq!(
+            parse_line("fn page(x) = x & ~0xfff").unwrap(),
+            Command::Define(function)
+        );
+        let call = Expr::Call(CallExpr {
+            name: String::from("add"),
+            args: vec![
+                literal_expr(1, 4..5),
+                Expr::BinOp(BinOpExpr {
+                    left: Box::new(literal_expr(2, 7..8)),
+                    right: Box::new(literal_expr(3, 11..12)),
+                    op: Op::Mul,
+                }),
+            ],
+        });
+        assert_eq!(parse_line("add(1, 2 * 3)").unwrap(), Command::Expr(call));
+        match parse_line("fn zero() = 0").unwrap() {
+            Command::Define(function) => assert!(function.params.is_empty()),
+            _ => panic!("Should have parsed to a function"),
+        }
+        assert!(parse_line("fn fn(x) = x").is_err());
+    }
+
+    #[test]
+    fn test_function_eval() {
+        let mut env = Env::default();
+        for line in [
+            "fn page(x) = x & ~0xfff",
+            "fn bit(n) = 1 << n",
+            "fn mask(lo, hi) = bit(hi + 1) - bit(lo)",
+            "fn scaled(x) = x * scale",
+            "fn forever(x) = forever(x + 1)",
+        ] {
+            match parse_line(line).unwrap() {
+                Command::Define(function) => env.define(function).unwrap(),
+                _ => panic!("Should have parsed to a function"),
+            }
+        }
+        env.set_var("scale", Value::Fixed(3), Dim::Count);
+        env.set_var("n", Value::Fixed(100), Dim::Count);
+
+        let eval = |expr_str, env: &Env| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, env),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        assert_eq!(eval("page(0x12345)", &env), Ok(0x12000));
+        assert_eq!(eval("bit(4) + n", &env), Ok(116));
+        assert_eq!(eval("mask(4, 7)", &env), Ok(0xf0));
+        assert_eq!(eval("scaled(bit(2))", &env), Ok(12));
+        assert_eq!(
+            eval("bit(1, 2)", &env),
+            Err(CorkError::Eval(String::from(
+                "bit takes 1 argument(s) but 2 were given"
+            )))
+        );
+        assert_eq!(
+            eval("nope(1)", &env),
+            Err(CorkError::Eval(String::from("nope is not a function")))
+        );
+        assert_eq!(
+            eval("forever(0)", &env),
+            Err(CorkError::Eval(format!(
+                "forever exceeded the maximum call depth of {}",
+                MAX_CALL_DEPTH
+            )))
+        );
+
+        match parse_line("fn twice(x, x) = x + x").unwrap() {
+            Command::Define(function) => assert!(env.define(function).is_err()),
+            _ => panic!("Should have parsed to a function"),
+        }
+    }
+
+    #[test]
+    fn test_builtin_eval() {
+        let word = |bits: u32, signed| Word::new(bits.try_into().unwrap(), signed);
+        let cases = [
+            ("popcnt(0xf0f0)", word(16, false), 8),
+            ("popcnt(-1)", word(32, true), 32),
+            ("clz(1)", word(8, false), 7),
+            ("clz(0)", word(64, true), 64),
+            ("clz(-1)", word(16, true), 0),
+            ("ctz(0x100)", word(32, false), 8),
+            ("ctz(0)", word(16, false), 16),
+            ("bswap(0x1234)", word(16, false), 0x3412),
+            ("bswap(0x12345678)", word(32, false), 0x78563412),
+            ("bswap(0x80)", word(16, true), -0x8000),
+            ("bswap16(0x1234)", word(16, false), 0x3412),
+            ("bswap16(0xabcd1234)", word(32, false), 0x3412),
+            ("bswap32(0x12345678)", word(64, false), 0x78563412),
+            (
+                "bswap64(0x0102030405060708)",
+                word(64, false),
+                0x0807060504030201,
+            ),
+            ("bswap64(0x80)", word(64, true), i64::MIN as i128),
+            ("bitrev(1)", word(8, false), 0x80),
+            ("bitrev(0x0f)", word(16, false), 0xf000),
+            ("rotl(0x81, 1)", word(8, false), 0x03),
+            ("rotl(0x81, 9)", word(8, false), 0x03),
+            ("rotl(0x81, -1)", word(8, false), 0xc0),
+            ("rotr(0x81, 1)", word(8, false), 0xc0),
+            ("rotr(1, 1)", word(64, true), i64::MIN as i128),
+            ("rotl(0x1234, 0)", word(16, false), 0x1234),
+            ("rotr(0x81, -1)", word(8, false), 0x03),
+            ("rotr(3, 1 << 127)", word(128, true), 3),
+            ("rotr(3, 1 << 127)", word(128, false), 3),
+            ("rotr(3, (1 << 127) + 1)", word(128, false), (1 << 127) | 1),
+            ("parity(0b111)", word(8, false), 1),
+            ("parity(0b11)", word(8, false), 0),
+        ];
+        for (expr_str, word, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(
+                    eval_fixed(&expr, &Env::new(word, Overflow::Wrapping)).unwrap(),
+                    expected,
+                    "{} in {}",
+                    expr_str,
+                    word
+                ),
+                _ => panic!("Should have parsed to an expr"),
+            }
+        }
+
+        match parse_line("rotl(1)").unwrap() {
+            Command::Expr(expr) => assert_eq!(
+                eval_fixed(&expr, &Env::default()),
+                Err(CorkError::Eval(String::from(
+                    "rotl takes 2 argument(s) but 1 were given"
+                )))
+            ),
+            _ => panic!("Should have parsed to an expr"),
+        }
+        match parse_line("bswap32(1)").unwrap() {
+            Command::Expr(expr) => assert_eq!(
+                eval_fixed(&expr, &Env::new(word(16, false), Overflow::Wrapping)),
+                Err(CorkError::Eval(String::from(
+                    "bswap32 needs a word of at least 32 bits"
+                )))
+            ),
+            _ => panic!("Should have parsed to an expr"),
+        }
+        match parse_line("fn popcnt(x) = x").unwrap() {
+            Command::Define(function) => assert_eq!(
+                Env::default().define(function),
+                Err(CorkError::Eval(String::from(
+                    "popcnt is a built-in function"
+                )))
+            ),
+            _ => panic!("Should have parsed to a function"),
+        }
+    }
+
+    #[test]
+    fn test_slice_parse() {
+        let expr1 = Expr::UnOp(UnOpExpr {
+            expr: Box::new(Expr::Slice(SliceExpr {
+                expr: Box::new(Expr::Var(String::from("x"))),
+                range: BitRange {
+                    hi: Box::new(literal_expr(7, 3..4)),
+                    lo: Some(Box::new(literal_expr(4, 5..6))),
+                },
+            })),
+            op: UnOp::Not,
         });
-        let expr_str1 = "(5 + 6) * 2";
-        assert_eq!(parse_line(expr_str1).unwrap(), Command::Expr(expr1));
-        let expr2 = Expr::BinOp(BinOpExpr {
-            right: Box::new(Expr::BinOp(BinOpExpr {
-                left: Box::new(Expr::Num(5)),
-                right: Box::new(Expr::Num(6)),
+        assert_eq!(parse_line("~x[7:4]").unwrap(), Command::Expr(expr1));
+        let expr2 = Expr::Slice(SliceExpr {
+            expr: Box::new(Expr::BinOp(BinOpExpr {
+                left: Box::new(literal_expr(1, 1..2)),
+                right: Box::new(literal_expr(2, 5..6)),
                 op: Op::Add,
             })),
-            left: Box::new(Expr::Num(2)),
-            op: Op::Mul,
+            range: BitRange {
+                hi: Box::new(Expr::Var(String::from("n"))),
+                lo: None,
+            },
         });
-        let expr_str2 = "2 * (5 + 6)";
-        assert_eq!(parse_line(expr_str2).unwrap(), Command::Expr(expr2));
+        assert_eq!(parse_line("(1 + 2)[n]").unwrap(), Command::Expr(expr2));
+        let assign = Assignment {
+            name: String::from("x"),
+            range: Some(BitRange {
+                hi: Box::new(literal_expr(3, 2..3)),
+                lo: None,
+            }),
+            expr: literal_expr(1, 7..8),
+        };
+        assert_eq!(parse_line("x[3] = 1").unwrap(), Command::Assign(assign));
     }
 
     #[test]
-    fn test_expr_eval() {
-        let expr1_str = "(5 + 6) * 2";
-        match parse_line(expr1_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 22),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr2_str = "2 * (5 + 6)";
-        match parse_line(expr2_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 22),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr3_str = "3 * (9 + 6) - 4";
-        match parse_line(expr3_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 41),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr4_str = "6-57*(18+4/73)+38 *  124";
-        match parse_line(expr4_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 3692)
-            }
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr5_str = "2 + (((7 * 2) - 4) / 2) + 8 * 9 / 4";
-        match parse_line(expr5_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 25),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr6_str = "(3 + 2) - 1 / 1 * 3 + 5 * 4 / 10 - 1";
-        match parse_line(expr6_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 3),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        let expr7_str = "8 / 2 * 3 - 9 - 6 * (15 / 3 / 5)";
-        match parse_line(expr7_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), -3),
+    fn test_slice_eval() {
+        let mut env = Env::new(Word::new(32.try_into().unwrap(), false), Overflow::Wrapping);
+        env.set_var("x", Value::Fixed(0xdead_beef), Dim::Count);
+        let eval = |expr_str, env: &Env| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, env),
             _ => panic!("Should have parsed to an expr"),
         };
-        let expr8_str = "24 / (2 * (12 / 4)) - ((8 * 3) / 6)";
-        match parse_line(expr8_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 0),
-            _ => panic!("Should have parsed to an expr"),
+        let assign = |line, env: &Env| match parse_line(line).unwrap() {
+            Command::Assign(assign) => assign.value(env).map(|value| value.to_fixed(env.word())),
+            _ => panic!("Should have parsed to an assignment"),
         };
-        let expr9_str = "3 * 512 >> 4 - 2";
-        match parse_line(expr9_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 384)
+
+        assert_eq!(eval("x[7:4]", &env), Ok(0xe));
+        assert_eq!(eval("x[31:16]", &env), Ok(0xdead));
+        assert_eq!(eval("x[31:0]", &env), Ok(0xdead_beef));
+        assert_eq!(eval("x[4]", &env), Ok(0));
+        assert_eq!(eval("x[0] + x[1]", &env), Ok(2));
+        assert_eq!(eval("x[15:8][3:0]", &env), Ok(0xe));
+        assert_eq!(eval("setbits(x, 15, 0, 0x1234)", &env), Ok(0xdead_1234));
+        assert_eq!(eval("setbits(x, 3, 0, 0x12)", &env), Ok(0xdead_bee2));
+        assert_eq!(
+            eval("x[32]", &env),
+            Err(CorkError::BitOutOfRange {
+                index: String::from("32"),
+                word: env.word(),
+            })
+        );
+        assert_eq!(
+            eval("x[-1:0]", &env),
+            Err(CorkError::BitOutOfRange {
+                index: String::from("4294967295"),
+                word: env.word(),
+            })
+        );
+        assert_eq!(
+            eval("x[3:4]", &env),
+            Err(CorkError::Eval(String::from(
+                "high bit 3 is below low bit 4"
+            )))
+        );
+
+        assert_eq!(assign("x[3:0] = 0", &env), Ok(0xdead_bee0));
+        assert_eq!(assign("x[31] = 0", &env), Ok(0x5ead_beef));
+        assert_eq!(
+            assign("y[1] = 1", &env),
+            Err(CorkError::Eval(String::from("y is not defined")))
+        );
+
+        env.set_word(Word::new(8.try_into().unwrap(), true));
+        env.set_var("y", Value::Fixed(0x7f), Dim::Count);
+        assert_eq!(assign("y[7] = 1", &env), Ok(-1));
+        assert_eq!(eval("y[7:4]", &env), Ok(7));
+    }
+
+    #[test]
+    fn test_cond_parse() {
+        let expr = Expr::Cond(CondExpr {
+            cond: Box::new(Expr::BinOp(BinOpExpr {
+                left: Box::new(Expr::BinOp(BinOpExpr {
+                    left: Box::new(Expr::Var(String::from("addr"))),
+                    right: Box::new(literal_expr(0xfff, 8..13)),
+                    op: Op::And,
+                })),
+                right: Box::new(literal_expr(0, 18..19)),
+                op: Op::Eq,
+            })),
+            if_true: Box::new(literal_expr(1, 22..23)),
+            if_false: Box::new(Expr::Cond(CondExpr {
+                cond: Box::new(Expr::BinOp(BinOpExpr {
+                    left: Box::new(Expr::Var(String::from("a"))),
+                    right: Box::new(Expr::Var(String::from("b"))),
+                    op: Op::LogicalOr,
+                })),
+                if_true: Box::new(literal_expr(2, 35..36)),
+                if_false: Box::new(literal_expr(3, 39..40)),
+            })),
+        });
+        assert_eq!(
+            parse_line("(addr & 0xfff) == 0 ? 1 : a || b ? 2 : 3").unwrap(),
+            Command::Expr(expr)
+        );
+    }
+
+    #[test]
+    fn test_comparison_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let cases = [
+            ("(0x1000 & 0xfff) == 0", Word::default(), 1),
+            ("(0x1001 & 0xfff) == 0", Word::default(), 0),
+            ("3 != 4", Word::default(), 1),
+            ("0xff < 1", i8, 1),
+            ("0xff < 1", u8, 0),
+            ("0x80 >= 0x7f", i8, 0),
+            ("0x80 >= 0x7f", u8, 1),
+            ("2 <= 2", i8, 1),
+            ("2 > 2", i8, 0),
+            ("1 < 2 == 1", i8, 1),
+            ("1 + 1 == 2 && 3 > 2", i8, 1),
+            ("0 || 5", i8, 1),
+            ("0 && 5", i8, 0),
+            ("1 << 2 < 5", i8, 1),
+            ("4 & 4 == 4", i8, 0),
+            ("1 ? 10 : 20", i8, 10),
+            ("0 ? 10 : 20", i8, 20),
+            ("0 ? 10 : 0 ? 20 : 30", i8, 30),
+            ("1 ? 2 ? 3 : 4 : 5", i8, 3),
+        ];
+        for (expr_str, word, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(
+                    eval_fixed(&expr, &Env::new(word, Overflow::Wrapping)).unwrap(),
+                    expected,
+                    "{} in {}",
+                    expr_str,
+                    word
+                ),
+                _ => panic!("Should have parsed to an expr"),
             }
+        }
+    }
+
+    #[test]
+    fn test_short_circuit_eval() {
+        let mut env = Env::default();
+        match parse_line("fn fact(n) = n <= 1 ? 1 : n * fact(n - 1)").unwrap() {
+            Command::Define(function) => env.define(function).unwrap(),
+            _ => panic!("Should have parsed to a function"),
+        }
+        let eval = |expr_str, env: &Env| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, env),
             _ => panic!("Should have parsed to an expr"),
         };
-        let expr10_str = "3 * (512 >> 4) - 2";
-        match parse_line(expr10_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 94),
-            _ => panic!("Should have parsed to an expr"),
-        };
-        // testing just the bitwise AND
-        let expr11_str = "0b0011 & 0b0110";
-        match parse_line(expr11_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 0b0010)
-            }
-            _ => panic!("Should have parsed to an expr"),
-        }
-        // testing just the bitwise OR
-        let expr12_str = "0b0011 | 0b0110";
-        match parse_line(expr12_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 0b0111)
+        assert_eq!(eval("fact(10)", &env), Ok(3628800));
+        assert_eq!(eval("0 && 1 / 0", &env), Ok(0));
+        assert_eq!(eval("1 || undefined", &env), Ok(1));
+        assert_eq!(eval("1 ? 2 : 1 / 0", &env), Ok(2));
+        assert!(eval("1 && 1 / 0", &env).is_err());
+    }
+
+    #[test]
+    fn test_pow_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let cases = [
+            ("2 ** 20", Word::default(), Overflow::Checked, Ok(1 << 20)),
+            ("2 ** 3 ** 2", Word::default(), Overflow::Checked, Ok(512)),
+            ("-2 ** 2", Word::default(), Overflow::Checked, Ok(-4)),
+            ("(-2) ** 7", i8, Overflow::Checked, Ok(-128)),
+            ("3 * 2 ** 2", i8, Overflow::Checked, Ok(12)),
+            ("2 ** 0", i8, Overflow::Checked, Ok(1)),
+            ("2 ** 8", u8, Overflow::Wrapping, Ok(0)),
+            ("2 ** 8", u8, Overflow::Saturating, Ok(0xff)),
+            ("(-2) ** 9", i8, Overflow::Saturating, Ok(-128)),
+            ("pow(3, 4)", i8, Overflow::Checked, Ok(81)),
+            (
+                "2 ** 7",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Overflow {
+                    op: String::from("**"),
+                    operands: vec![String::from("2"), String::from("7")],
+                    word: i8,
+                }),
+            ),
+            (
+                "2 ** -1",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Eval(String::from(
+                    "Cannot raise to a negative power",
+                ))),
+            ),
+        ];
+        for (expr_str, word, overflow, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(
+                    eval_fixed(&expr, &Env::new(word, overflow)),
+                    expected,
+                    "{} in {}",
+                    expr_str,
+                    word
+                ),
+                _ => panic!("Should have parsed to an expr"),
             }
-            _ => panic!("Should have parsed to an expr"),
         }
-        // testing just the bitwise XOR
-        let expr13_str = "0b0011 ^ 0b0101";
-        match parse_line(expr13_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 0b0110)
+    }
+
+    #[test]
+    fn test_math_builtin_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let u128 = Word::new(128.try_into().unwrap(), false);
+        let cases = [
+            ("gcd(12, 18)", i8, Overflow::Checked, Ok(6)),
+            ("gcd(-12, 18)", i8, Overflow::Checked, Ok(6)),
+            ("gcd(0, 5)", i8, Overflow::Checked, Ok(5)),
+            ("lcm(4, 6)", i8, Overflow::Checked, Ok(12)),
+            ("lcm(0, 6)", i8, Overflow::Checked, Ok(0)),
+            ("lcm(16, 17)", u8, Overflow::Saturating, Ok(0xff)),
+            ("lcm(16, 24)", u8, Overflow::Wrapping, Ok(48)),
+            (
+                "lcm(1 << 127, 3 << 100)",
+                u128,
+                Overflow::Wrapping,
+                Ok(1 << 127),
+            ),
+            ("isqrt(99)", i8, Overflow::Checked, Ok(9)),
+            ("isqrt(0xff)", u8, Overflow::Checked, Ok(15)),
+            ("log2(1)", i8, Overflow::Checked, Ok(0)),
+            ("log2(4096)", Word::default(), Overflow::Checked, Ok(12)),
+            ("log2(4097)", Word::default(), Overflow::Checked, Ok(12)),
+            ("log2(0x80)", u8, Overflow::Checked, Ok(7)),
+            (
+                "align_up(0x1001, 4096)",
+                Word::default(),
+                Overflow::Checked,
+                Ok(0x2000),
+            ),
+            (
+                "align_up(0x1000, 4096)",
+                Word::default(),
+                Overflow::Checked,
+                Ok(0x1000),
+            ),
+            ("align_up(10, 3)", i8, Overflow::Checked, Ok(12)),
+            (
+                "align_up(1, 1 << 127)",
+                u128,
+                Overflow::Checked,
+                Ok(1 << 127),
+            ),
+            (
+                "align_up(1, 3 << 126)",
+                u128,
+                Overflow::Checked,
+                Ok(3 << 126),
+            ),
+            (
+                "align_down(0x1fff, 4096)",
+                Word::default(),
+                Overflow::Checked,
+                Ok(0x1000),
+            ),
+            ("align_down(-1, 16)", i8, Overflow::Checked, Ok(-16)),
+            ("align_up(0xf1, 0x10)", u8, Overflow::Wrapping, Ok(0)),
+            (
+                "gcd(-127 - 1, 0)",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Overflow {
+                    op: String::from("gcd"),
+                    operands: vec![String::from("-128"), String::from("0")],
+                    word: i8,
+                }),
+            ),
+            (
+                "align_up(0xf1, 0x10)",
+                u8,
+                Overflow::Checked,
+                Err(CorkError::Overflow {
+                    op: String::from("align_up"),
+                    operands: vec![String::from("241"), String::from("16")],
+                    word: u8,
+                }),
+            ),
+            (
+                "align_down(5, 0)",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Eval(String::from(
+                    "align_down needs a positive alignment",
+                ))),
+            ),
+            (
+                "log2(0)",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Eval(String::from(
+                    "log2 is only defined for positive numbers",
+                ))),
+            ),
+            (
+                "isqrt(-4)",
+                i8,
+                Overflow::Checked,
+                Err(CorkError::Eval(String::from(
+                    "isqrt is not defined for negative numbers",
+                ))),
+            ),
+        ];
+        for (expr_str, word, overflow, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(
+                    eval_fixed(&expr, &Env::new(word, overflow)),
+                    expected,
+                    "{} in {}",
+                    expr_str,
+                    word
+                ),
+                _ => panic!("Should have parsed to an expr"),
             }
-            _ => panic!("Should have parsed to an expr"),
         }
-        // testing all of the bitwise operators together
-        let expr14_str = "((0b0011 ^ 0b0101) & 0b0011) | 0b0111";
-        match parse_line(expr14_str).unwrap() {
-            Command::Expr(expr) => {
-                assert_eq!(eval_expr(&expr, 0).unwrap(), 0b0111)
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_eval() {
+        use num_bigint::BigInt;
+
+        let mut env = Env::new(Word::new(8.try_into().unwrap(), true), Overflow::Checked);
+        env.set_var("x", Value::Fixed(-2), Dim::Count);
+        env.set_ans(Value::Fixed(0x7f), Dim::Count);
+        env.set_big(true);
+        let big = |num: &str| Ok(Value::Big(num.parse::<BigInt>().unwrap()));
+        let cases = [
+            ("2 ** 128", big("340282366920938463463374607431768211456")),
+            (
+                "0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff + 1",
+                big("340282366920938463463374607431768211456"),
+            ),
+            ("1 << 100 >> 99", big("2")),
+            ("-(1 << 100) >> 100", big("-1")),
+            ("~0 & 0xff", big("255")),
+            ("(1 << 70)[70:69]", big("2")),
+            ("(-1)[99:0] == (1 << 100) - 1", big("1")),
+            ("x * ans", big("-254")),
+            ("-7 / 2", big("-3")),
+            ("-7 % 2", big("-1")),
+            ("gcd(2 ** 100, 6 ** 50)", big("1125899906842624")),
+            ("isqrt(10 ** 40)", big("100000000000000000000")),
+            ("log2(2 ** 200)", big("200")),
+            (
+                "align_up(1 << 100, 3)",
+                big("1267650600228229401496703205378"),
+            ),
+            ("align_down(-1, 16)", big("-16")),
+            ("1000s @ 1MHz", big("1000000000")),
+            ("10 ** 12 @ 1MHz", big("1000000000000000")),
+            ("1 < 2 && 2 ** 100 > 2 ** 99", big("1")),
+            ("(-1) ** 101 + 1 ** (1 << 100)", big("0")),
+            (
+                "2 ** 100000",
+                Err(CorkError::Eval(String::from(
+                    "The result of ** does not fit in 65536 bits",
+                ))),
+            ),
+            (
+                "1 << 65536",
+                Err(CorkError::Eval(String::from(
+                    "The result of << does not fit in 65536 bits",
+                ))),
+            ),
+            (
+                "popcnt(1)",
+                Err(CorkError::Eval(String::from(
+                    "popcnt needs a word of fixed width",
+                ))),
+            ),
+            (
+                "x[-1]",
+                Err(CorkError::Eval(String::from(
+                    "bit -1 is out of range for big numbers",
+                ))),
+            ),
+        ];
+        for (expr_str, expected) in cases {
+            match parse_line(expr_str).unwrap() {
+                Command::Expr(expr) => assert_eq!(eval_expr(&expr, &env), expected, "{}", expr_str),
+                _ => panic!("Should have parsed to an expr"),
             }
-            _ => panic!("Should have parsed to an expr"),
         }
-        // mixing bitwise and "normal" operators
-        let expr15_str = "(((0b0011 ^ 0b0101) * 2) & 0b0101) + 1";
-        match parse_line(expr15_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 5),
-            _ => panic!("Should have parsed to an expr"),
+
+        match parse_line("x[127:120] = 0xab").unwrap() {
+            Command::Assign(assign) => assert_eq!(
+                assign.value(&env),
+                big("-111655151645932933323919793063548944386")
+            ),
+            _ => panic!("Should have parsed to an assignment"),
         }
-        // testing operator precedence / priority with bitwise ops
-        let expr16_str = "0b0100 ^ 0b0000 | 0b0101 * 2 & 0b0101 + 1";
-        match parse_line(expr16_str).unwrap() {
-            Command::Expr(expr) => assert_eq!(eval_expr(&expr, 0).unwrap(), 6),
+
+        // big values are truncated once the width is fixed again
+        env.set_big(false);
+        env.set_ans(
+            big("340282366920938463463374607431768211457").unwrap(),
+            Dim::Count,
+        );
+        match parse_line("ans").unwrap() {
+            Command::Expr(expr) => assert_eq!(eval_expr(&expr, &env), Ok(Value::Fixed(1))),
             _ => panic!("Should have parsed to an expr"),
         }
     }
@@ -434,56 +3375,56 @@ mod test {
         let conv1 = ConvDirective {
             expr: Expr::BinOp(BinOpExpr {
                 left: Box::new(Expr::BinOp(BinOpExpr {
-                    left: Box::new(Expr::Num(5)),
-                    right: Box::new(Expr::Num(6)),
+                    left: Box::new(literal_expr(5, 1..2)),
+                    right: Box::new(literal_expr(6, 5..6)),
                     op: Op::Add,
                 })),
-                right: Box::new(Expr::Num(2)),
+                right: Box::new(literal_expr(2, 10..11)),
                 op: Op::Mul,
             }),
-            radix: FormatRadix::Hex,
+            target: Target::Radix(FormatRadix::Hex),
         };
         assert_eq!(parse_line(conv_str).unwrap(), Command::Convert(conv1));
 
         let conv2 = ConvDirective {
             expr: Expr::BinOp(BinOpExpr {
                 left: Box::new(Expr::BinOp(BinOpExpr {
-                    left: Box::new(Expr::Num(5)),
-                    right: Box::new(Expr::Num(6)),
+                    left: Box::new(literal_expr(5, 1..2)),
+                    right: Box::new(literal_expr(6, 5..6)),
                     op: Op::Add,
                 })),
-                right: Box::new(Expr::Num(2)),
+                right: Box::new(literal_expr(2, 10..11)),
                 op: Op::Mul,
             }),
-            radix: FormatRadix::Decimal,
+            target: Target::Radix(FormatRadix::Decimal),
         };
         assert_ne!(parse_line(conv_str).unwrap(), Command::Convert(conv2));
 
         let conv3 = ConvDirective {
             expr: Expr::BinOp(BinOpExpr {
                 left: Box::new(Expr::BinOp(BinOpExpr {
-                    left: Box::new(Expr::Num(5)),
-                    right: Box::new(Expr::Num(6)),
+                    left: Box::new(literal_expr(5, 1..2)),
+                    right: Box::new(literal_expr(6, 5..6)),
                     op: Op::Add,
                 })),
-                right: Box::new(Expr::Num(2)),
+                right: Box::new(literal_expr(2, 10..11)),
                 op: Op::Mul,
             }),
-            radix: FormatRadix::Octal,
+            target: Target::Radix(FormatRadix::Octal),
         };
         assert_ne!(parse_line(conv_str).unwrap(), Command::Convert(conv3));
 
         let conv4 = ConvDirective {
             expr: Expr::BinOp(BinOpExpr {
                 left: Box::new(Expr::BinOp(BinOpExpr {
-                    left: Box::new(Expr::Num(5)),
-                    right: Box::new(Expr::Num(6)),
+                    left: Box::new(literal_expr(5, 1..2)),
+                    right: Box::new(literal_expr(6, 5..6)),
                     op: Op::Add,
                 })),
-                right: Box::new(Expr::Num(2)),
+                right: Box::new(literal_expr(2, 10..11)),
                 op: Op::Mul,
             }),
-            radix: FormatRadix::Binary,
+            target: Target::Radix(FormatRadix::Binary),
         };
         assert_ne!(parse_line(conv_str).unwrap(), Command::Convert(conv4));
     }
@@ -494,7 +3435,7 @@ mod test {
         let expr1_str = "(5 + 6) * 2 to dec";
         match parse_line(expr1_str).unwrap() {
             Command::Convert(conv) => {
-                assert_eq!(eval_expr(&conv.expr, 0).unwrap(), 22)
+                assert_eq!(eval_fixed(&conv.expr, &Env::default()).unwrap(), 22)
             }
             _ => panic!("Should have parsed to a conversion"),
         };
@@ -503,7 +3444,7 @@ mod test {
         let expr2_str = "(5 + 6) * 2 to bin";
         match parse_line(expr2_str).unwrap() {
             Command::Convert(conv) => {
-                assert_eq!(eval_expr(&conv.expr, 0).unwrap(), 22)
+                assert_eq!(eval_fixed(&conv.expr, &Env::default()).unwrap(), 22)
             }
             _ => panic!("Should have parsed to a conversion"),
         };
@@ -512,7 +3453,7 @@ mod test {
         let expr3_str = "(5 + 6) * 2 to hex";
         match parse_line(expr3_str).unwrap() {
             Command::Convert(conv) => {
-                assert_eq!(eval_expr(&conv.expr, 0).unwrap(), 22)
+                assert_eq!(eval_fixed(&conv.expr, &Env::default()).unwrap(), 22)
             }
             _ => panic!("Should have parsed to a conversion"),
         };
@@ -521,7 +3462,7 @@ mod test {
         let expr4_str = "(5 + 6) * 2 to oct";
         match parse_line(expr4_str).unwrap() {
             Command::Convert(conv) => {
-                assert_eq!(eval_expr(&conv.expr, 0).unwrap(), 22)
+                assert_eq!(eval_fixed(&conv.expr, &Env::default()).unwrap(), 22)
             }
             _ => panic!("Should have parsed to a conversion"),
         };
@@ -587,9 +3528,10 @@ mod test {
             Command::Convert(conversion) => {
                 let result = format!(
                     "{:?}",
-                    OutputFormat::default()
-                        .with_format_radix(conversion.radix())
-                        .fmt(conversion.value(0).unwrap())
+                    OutputFormat::default().fmt_to(
+                        conversion.target(),
+                        &conversion.value(&Env::default()).unwrap()
+                    )
                 );
                 assert_eq!(result, "\"127\"");
             }
@@ -601,9 +3543,10 @@ mod test {
             Command::Convert(conversion) => {
                 let result = format!(
                     "{:?}",
-                    OutputFormat::default()
-                        .with_format_radix(conversion.radix())
-                        .fmt(conversion.value(0).unwrap())
+                    OutputFormat::default().fmt_to(
+                        conversion.target(),
+                        &conversion.value(&Env::default()).unwrap()
+                    )
                 );
                 assert_eq!(result, "\"0x7f\"");
             }
@@ -615,9 +3558,10 @@ mod test {
             Command::Convert(conversion) => {
                 let result = format!(
                     "{:?}",
-                    OutputFormat::default()
-                        .with_format_radix(conversion.radix())
-                        .fmt(conversion.value(0).unwrap())
+                    OutputFormat::default().fmt_to(
+                        conversion.target(),
+                        &conversion.value(&Env::default()).unwrap()
+                    )
                 );
                 assert_eq!(result, "\"0o177\"");
             }
@@ -629,9 +3573,10 @@ mod test {
             Command::Convert(conversion) => {
                 let result = format!(
                     "{:?}",
-                    OutputFormat::default()
-                        .with_format_radix(conversion.radix())
-                        .fmt(conversion.value(0).unwrap())
+                    OutputFormat::default().fmt_to(
+                        conversion.target(),
+                        &conversion.value(&Env::default()).unwrap()
+                    )
                 );
                 assert_eq!(result, "\"0b1111111\"");
             }
@@ -642,43 +3587,94 @@ mod test {
     #[test]
     fn hex_parse() {
         let hex_str1 = "0x1a";
-        assert_eq!(parse_line(hex_str1).unwrap(), Command::Expr(Expr::Num(26)));
+        assert_eq!(
+            parse_line(hex_str1).unwrap(),
+            Command::Expr(literal_expr(26, 0..4))
+        );
         let hex_str2 = "0xCAFE";
         assert_eq!(
             parse_line(hex_str2).unwrap(),
-            Command::Expr(Expr::Num(51966))
+            Command::Expr(literal_expr(51966, 0..6))
         );
         let hex_str3 = "0xFACE_A0CE";
         assert_eq!(
             parse_line(hex_str3).unwrap(),
-            Command::Expr(Expr::Num(4207845582))
+            Command::Expr(literal_expr(4207845582, 0..11))
+        );
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_parse() {
+        assert_eq!(
+            parse_line("0x7fff_ffff_ffff_ffff_ffff_ffff_ffff_ffff").unwrap(),
+            Command::Expr(literal_expr(i128::MAX, 0..41))
+        );
+        assert_eq!(
+            parse_line("0x1_0000_0000_0000_0000_0000_0000_0000_0000").unwrap(),
+            Command::Expr(Expr::Literal(LiteralExpr {
+                value: Value::Big(num_bigint::BigInt::from(u128::MAX) + 1u8),
+                span: 0..43,
+            }))
         );
     }
 
+    #[test]
+    fn test_separator_parse() {
+        let cases = [
+            ("1'000'000", 1_000_000),
+            ("0xdead'beef", 0xdead_beef),
+            ("0o7'7_7", 0o777),
+            ("0b1010'0101", 0b1010_0101),
+        ];
+        for (literal, num) in cases {
+            assert_eq!(
+                parse_line(literal).unwrap(),
+                Command::Expr(literal_expr(num, 0..literal.len()))
+            );
+        }
+        assert!(parse_line("1''000").is_err());
+        assert!(parse_line("1000'").is_err());
+        assert!(parse_line("1,000").is_err());
+        assert!(parse_line("0b10'2").is_err());
+    }
+
     #[test]
     fn oct_parse() {
         let oct_str1 = "0o345";
-        assert_eq!(parse_line(oct_str1).unwrap(), Command::Expr(Expr::Num(229)));
+        assert_eq!(
+            parse_line(oct_str1).unwrap(),
+            Command::Expr(literal_expr(229, 0..5))
+        );
         let oct_str2 = "0o1232344";
         assert_eq!(
             parse_line(oct_str2).unwrap(),
-            Command::Expr(Expr::Num(341220))
+            Command::Expr(literal_expr(341220, 0..9))
         );
         let oct_str3 = "0o1232_34_4";
         assert_eq!(
             parse_line(oct_str3).unwrap(),
-            Command::Expr(Expr::Num(341220))
+            Command::Expr(literal_expr(341220, 0..11))
         );
     }
 
     #[test]
     fn bin_parse() {
         let bin_str1 = "0b1010";
-        assert_eq!(parse_line(bin_str1).unwrap(), Command::Expr(Expr::Num(10)));
+        assert_eq!(
+            parse_line(bin_str1).unwrap(),
+            Command::Expr(literal_expr(10, 0..6))
+        );
         let bin_str1 = "0b10100101";
-        assert_eq!(parse_line(bin_str1).unwrap(), Command::Expr(Expr::Num(165)));
+        assert_eq!(
+            parse_line(bin_str1).unwrap(),
+            Command::Expr(literal_expr(165, 0..10))
+        );
         let bin_str3 = "0b10_10_01____01";
-        assert_eq!(parse_line(bin_str3).unwrap(), Command::Expr(Expr::Num(165)));
+        assert_eq!(
+            parse_line(bin_str3).unwrap(),
+            Command::Expr(literal_expr(165, 0..16))
+        );
     }
 
     #[test]
@@ -686,7 +3682,605 @@ mod test {
         let dec_str1 = "1234_5678";
         assert_eq!(
             parse_line(dec_str1).unwrap(),
-            Command::Expr(Expr::Num(12345678))
+            Command::Expr(literal_expr(12345678, 0..9))
+        );
+    }
+
+    #[test]
+    fn test_based_parse() {
+        let cases = [
+            ("36#zz", 1295),
+            ("36#ZZ", 1295),
+            ("16#ff#", 255),
+            ("2#1_0_1", 5),
+            ("0r3:1210", 48),
+            ("0r36:a'b", 371),
+            ("0r10:99", 99),
+        ];
+        for (literal, num) in cases {
+            assert_eq!(
+                parse_line(literal),
+                Ok(Command::Expr(literal_expr(num, 0..literal.len()))),
+                "{}",
+                literal
+            );
+        }
+
+        let errors = [
+            (
+                "1 + 37#1",
+                CorkError::InvalidBase {
+                    span: 4..6,
+                    base: String::from("37"),
+                },
+            ),
+            (
+                "0r1:0",
+                CorkError::InvalidBase {
+                    span: 2..3,
+                    base: String::from("1"),
+                },
+            ),
+            (
+                "3#1_23",
+                CorkError::InvalidDigit {
+                    span: 5..6,
+                    digit: '3',
+                    base: 3,
+                },
+            ),
+            (
+                "0r16:fg",
+                CorkError::InvalidDigit {
+                    span: 6..7,
+                    digit: 'g',
+                    base: 16,
+                },
+            ),
+            (
+                "5 to base(37)",
+                CorkError::InvalidBase {
+                    span: 10..12,
+                    base: String::from("37"),
+                },
+            ),
+        ];
+        for (line, err) in errors {
+            assert_eq!(parse_line(line), Err(err), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_target_parse() {
+        let cases = [
+            (
+                "5 to base(36)",
+                Target::Radix(FormatRadix::Base(36.try_into().unwrap())),
+            ),
+            (
+                "5 to base( 3 )",
+                Target::Radix(FormatRadix::Base(3.try_into().unwrap())),
+            ),
+            ("5 to base(16)", Target::Radix(FormatRadix::Hex)),
+            ("5 to all", Target::All),
+            ("5 to bits", Target::Bits(BitOrder::Msb)),
+            ("5 to bits lsb", Target::Bits(BitOrder::Lsb)),
+            ("5 to bytes", Target::Bytes(Endian::Big)),
+            ("5 to bytes le", Target::Bytes(Endian::Little)),
+            ("5 to f32", Target::Float(FloatFormat::F32)),
+            ("5 to bf16", Target::Float(FloatFormat::Bf16)),
+            ("5 to q15", Target::Fixed("q15".parse().unwrap())),
+            ("5 to q16.16", Target::Fixed("q16.16".parse().unwrap())),
+            ("5 to size", Target::Size),
+            ("5 to ascii", Target::Ascii),
+            ("5 to str", Target::Str),
+        ];
+        for (line, target) in cases {
+            assert_eq!(
+                parse_line(line),
+                Ok(Command::Convert(ConvDirective {
+                    expr: literal_expr(5, 0..1),
+                    target,
+                })),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_chars_parse() {
+        let cases = [
+            (r"'A'", 0x41),
+            (r"'ELF'", 0x45_4c46),
+            (r"'ELF'be", 0x45_4c46),
+            (r"'\x7fELF'", 0x7f45_4c46),
+            (r"'RIFF'le", 0x4646_4952),
+            (r"'\n\r\t\0'", 0x0a0d_0900),
+            (r"'\'\\'", 0x275c),
+            (r"'é'", 0xc3a9),
+            (r"' '", 0x20),
+        ];
+        for (literal, num) in cases {
+            assert_eq!(
+                parse_line(literal),
+                Ok(Command::Expr(literal_expr(num, 0..literal.len()))),
+                "{}",
+                literal
+            );
+        }
+
+        for line in [r"''", r"'A", r"'\q'", r"'\x7'", r"'A'ne"] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+
+        // the digit separator is not taken for a character literal
+        assert_eq!(
+            parse_line("1'000"),
+            Ok(Command::Expr(literal_expr(1000, 0..5)))
+        );
+    }
+
+    #[test]
+    fn test_byte_list_parse() {
+        let cases = [
+            ("bytes(de ad be ef)", 0xdead_beef),
+            ("bytes(DE AD BE EF)be", 0xdead_beef),
+            ("bytes(deadbeef)", 0xdead_beef),
+            ("bytes( 7f 45 4c 46 )le", 0x464c_457f),
+            ("bytes(00 01)", 1),
+        ];
+        for (literal, num) in cases {
+            assert_eq!(
+                parse_line(literal),
+                Ok(Command::Expr(literal_expr(num, 0..literal.len()))),
+                "{}",
+                literal
+            );
+        }
+
+        for line in [
+            "bytes()",
+            "bytes(d)",
+            "bytes(de a)",
+            "bytes(de ag)",
+            "bytes(de)ne",
+        ] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_float_parse() {
+        let cases = [
+            ("f32(1.5)", 0x3fc0_0000),
+            ("f32( -2.5 )", 0xc020_0000),
+            ("f32(1e-45)", 1),
+            ("f32(.5E1)", 0x40a0_0000),
+            ("f64(1.)", 0x3ff0_0000_0000_0000),
+            ("f64(-inf)", 0xfff0_0000_0000_0000),
+            ("f16(1)", 0x3c00),
+            ("bf16(1)", 0x3f80),
+        ];
+        for (literal, num) in cases {
+            assert_eq!(
+                parse_line(literal),
+                Ok(Command::Expr(literal_expr(num, 0..literal.len()))),
+                "{}",
+                literal
+            );
+        }
+
+        for line in ["f32()", "f32(1.5.0)", "f32(e5)", "f32(- 1)", "f32 = 1"] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+        assert_eq!(
+            parse_line("1 + f16(1e5)"),
+            Err(CorkError::FloatOutOfRange {
+                span: 4..12,
+                format: FloatFormat::F16,
+            })
+        );
+    }
+
+    #[test]
+    fn test_sized_eval() {
+        let cases = [
+            ("4K", SizePrefixes::Binary, 0x1000),
+            ("4k", SizePrefixes::Binary, 0x1000),
+            ("2MiB", SizePrefixes::Binary, 0x20_0000),
+            ("1G", SizePrefixes::Binary, 0x4000_0000),
+            ("1G", SizePrefixes::Si, 1_000_000_000),
+            ("1GiB", SizePrefixes::Si, 0x4000_0000),
+            ("1kB", SizePrefixes::Si, 1000),
+            ("1'000KB", SizePrefixes::Si, 1_000_000),
+            ("3E", SizePrefixes::Binary, 3 << 60),
+            ("0x10 * 4K", SizePrefixes::Binary, 0x10000),
+        ];
+        for (line, size_prefixes, expected) in cases {
+            let mut env = Env::new(Word::new(64.try_into().unwrap(), false), Overflow::Wrapping);
+            env.set_size_prefixes(size_prefixes);
+            match parse_line(line).unwrap() {
+                Command::Expr(expr) => {
+                    assert_eq!(eval_fixed(&expr, &env), Ok(expected), "{}", line)
+                }
+                _ => panic!("Should have parsed to an expr"),
+            }
+        }
+
+        let u32 = Word::new(32.try_into().unwrap(), false);
+        let eval = |line, overflow| match parse_line(line).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(u32, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        assert_eq!(eval("4G", Overflow::Wrapping), Ok(0));
+        assert_eq!(eval("4G", Overflow::Saturating), Ok(0xffff_ffff));
+        assert_eq!(eval("3GiB", Overflow::Checked), Ok(0xc000_0000));
+        assert_eq!(
+            eval("8G", Overflow::Checked),
+            Err(CorkError::Overflow {
+                op: String::from("*"),
+                operands: vec![String::from("8"), String::from("1073741824")],
+                word: u32,
+            })
+        );
+
+        for line in ["4KiBs", "4 K", "4Q", "0x4K", "4Kb"] {
+            assert!(parse_line(line).is_err(), "{}", line);
+        }
+        assert_eq!(
+            parse_line("18446744073709551616K"),
+            Err(CorkError::LiteralOutOfRange {
+                span: 0..21,
+                max_bits: 64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_fixed_parse() {
+        let cases = [
+            ("q15(0.5)", 0x4000i128),
+            ("q15( -0.5 )", -0x4000),
+            ("q16.16(1.25)", 0x14000),
+            ("q31(-1)", -0x8000_0000),
+            ("q8.0(.5)", 1),
+            ("q15(0.000)", 0),
+        ];
+        for (literal, num) in cases {
+            let expr = literal_expr(num.abs(), 0..literal.len());
+            let expr = match num < 0 {
+                true => Expr::UnOp(UnOpExpr {
+                    expr: Box::new(expr),
+                    op: UnOp::Neg,
+                }),
+                false => expr,
+            };
+            assert_eq!(parse_line(literal), Ok(Command::Expr(expr)), "{}", literal);
+        }
+
+        let q15 = "q15".parse().unwrap();
+        let errors = [
+            (
+                "1 + q15(1)",
+                CorkError::FixedOutOfRange {
+                    span: 4..10,
+                    format: q15,
+                },
+            ),
+            (
+                "q15(0.00001)",
+                CorkError::FixedUnderflow {
+                    span: 0..12,
+                    format: q15,
+                },
+            ),
+            (
+                "q0.15(0.5)",
+                CorkError::InvalidFixedFormat {
+                    span: 0..5,
+                    format: String::from("q0.15"),
+                },
+            ),
+            (
+                "5 to q64",
+                CorkError::InvalidFixedFormat {
+                    span: 5..8,
+                    format: String::from("q64"),
+                },
+            ),
+        ];
+        for (line, error) in errors {
+            assert_eq!(parse_line(line), Err(error), "{}", line);
+        }
+        assert!(parse_line("q15(1e5)").is_err());
+    }
+
+    #[test]
+    fn test_fixed_eval() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let i16 = Word::new(16.try_into().unwrap(), true);
+        let eval = |expr_str, word, overflow| match parse_line(expr_str).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(word, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+        for overflow in [Overflow::Checked, Overflow::Wrapping, Overflow::Saturating] {
+            assert_eq!(eval("q15(-1)", i16, overflow), Ok(-0x8000));
+            assert_eq!(eval("q15(-0.5)", i16, overflow), Ok(-0x4000));
+        }
+        assert_eq!(
+            eval("1 + q15(0.5)", i8, Overflow::Checked),
+            Err(CorkError::LiteralOverflow {
+                span: 4..12,
+                word: i8,
+            })
+        );
+        assert_eq!(eval("q15(0.5)", i8, Overflow::Saturating), Ok(0x7f));
+        assert_eq!(
+            eval("q15(0.5)", i8, Overflow::Wrapping),
+            Err(CorkError::LiteralOutOfRange {
+                span: 0..8,
+                max_bits: 8,
+            })
+        );
+
+        let mut env = Env::default();
+        for name in ["q8", "q15"] {
+            match parse_line(format!("fn {}(x) = x", name)).unwrap() {
+                Command::Define(function) => assert_eq!(
+                    env.define(function),
+                    Err(CorkError::Eval(format!(
+                        "{} is reserved for fixed-point literals",
+                        name
+                    )))
+                ),
+                _ => panic!("Should have parsed to a function"),
+            }
+        }
+        match parse_line("fn q(x) = x").unwrap() {
+            Command::Define(function) => assert_eq!(env.define(function), Ok(())),
+            _ => panic!("Should have parsed to a function"),
+        }
+    }
+
+    #[cfg(not(feature = "bignum"))]
+    #[test]
+    fn test_chars_out_of_range() {
+        assert_eq!(
+            parse_line("1 + '0123456789abcdefg'"),
+            Err(CorkError::LiteralOutOfRange {
+                span: 4..23,
+                max_bits: 128,
+            })
+        );
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_chars_out_of_range() {
+        let large = format!("'{}'", "a".repeat(8192));
+        assert!(matches!(
+            parse_line(&large),
+            Ok(Command::Expr(Expr::Literal(LiteralExpr {
+                value: Value::Big(_),
+                ..
+            })))
+        ));
+        let too_large = format!("'{}'", "a".repeat(8193));
+        assert_eq!(
+            parse_line(&too_large),
+            Err(CorkError::LiteralOutOfRange {
+                span: 0..too_large.len(),
+                max_bits: MAX_BIG_BITS,
+            })
         );
     }
+
+    /// Returns the largest literal in each radix, along with the smallest one
+    /// that is out of range.
+    #[cfg(not(feature = "bignum"))]
+    fn boundary_literals() -> Vec<(String, String)> {
+        vec![
+            (
+                u128::MAX.to_string(),
+                String::from("340282366920938463463374607431768211456"),
+            ),
+            (
+                format!("0x{}", "f".repeat(32)),
+                format!("0x1{}", "0".repeat(32)),
+            ),
+            (
+                format!("0o3{}", "7".repeat(42)),
+                format!("0o4{}", "0".repeat(42)),
+            ),
+            (
+                format!("0b{}", "1".repeat(128)),
+                format!("0b1{}", "0".repeat(128)),
+            ),
+        ]
+    }
+
+    #[cfg(feature = "bignum")]
+    fn boundary_literals() -> Vec<(String, String)> {
+        let limit: num_bigint::BigInt = num_bigint::BigInt::from(1u8) << 65536;
+        vec![
+            ((&limit - 1u8).to_string(), limit.to_string()),
+            (
+                format!("0x{}", "f".repeat(16384)),
+                format!("0x1{}", "0".repeat(16384)),
+            ),
+            (
+                format!("0o1{}", "7".repeat(21845)),
+                format!("0o2{}", "0".repeat(21845)),
+            ),
+            (
+                format!("0b{}", "1".repeat(65536)),
+                format!("0b1{}", "0".repeat(65536)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_literal_out_of_range() {
+        let max_bits = if cfg!(feature = "bignum") { 65536 } else { 128 };
+        for (largest, too_large) in boundary_literals() {
+            assert!(
+                matches!(parse_line(&largest), Ok(Command::Expr(Expr::Literal(_)))),
+                "{} should be in range",
+                largest
+            );
+            let line = format!("1 + {} * 2", too_large);
+            assert_eq!(
+                parse_line(&line),
+                Err(CorkError::LiteralOutOfRange {
+                    span: 4..4 + too_large.len(),
+                    max_bits,
+                }),
+                "{} should be out of range",
+                too_large
+            );
+        }
+
+        // literals are checked wherever they appear
+        let too_large = format!("0x1{}", "0".repeat(16384));
+        for line in [
+            format!("x[{}] = 1", too_large),
+            format!("fn f(x) = x + {}", too_large),
+            format!("f({}) to hex", too_large),
+            format!("1 ? {} : 2", too_large),
+        ] {
+            assert!(
+                matches!(parse_line(&line), Err(CorkError::LiteralOutOfRange { .. })),
+                "{}",
+                line
+            );
+        }
+    }
+
+    #[test]
+    fn test_literal_word_boundary() {
+        let i64 = Word::default();
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let eval = |line: &str, word, overflow| match parse_line(line).unwrap() {
+            Command::Expr(expr) => eval_fixed(&expr, &Env::new(word, overflow)),
+            _ => panic!("Should have parsed to an expr"),
+        };
+
+        // when wrapping, a literal is read as the bits of the word, which it must fit in
+        let wrapping = [
+            (String::from("18446744073709551615"), "18446744073709551616"),
+            (format!("0x{}", "f".repeat(16)), "0x1_0000_0000_0000_0000"),
+            (format!("0o1{}", "7".repeat(21)), "0o2_000_000_000_000_000_000_000"),
+            (format!("0b{}", "1".repeat(64)), "0b1_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000"),
+            (format!("16#{}#", "f".repeat(16)), "16#1_0000_0000_0000_0000#"),
+            (String::from("'ABCDEFGH'"), "'ABCDEFGHI'"),
+            (String::from("bytes(ff ff ff ff ff ff ff ff)"), "bytes(01 00 00 00 00 00 00 00 00)"),
+        ];
+        for (largest, too_large) in wrapping {
+            assert!(
+                eval(&largest, i64, Overflow::Wrapping).is_ok(),
+                "{} should be in range",
+                largest
+            );
+            assert_eq!(
+                eval(too_large, i64, Overflow::Wrapping),
+                Err(CorkError::LiteralOutOfRange {
+                    span: 0..too_large.len(),
+                    max_bits: 64,
+                }),
+                "{}",
+                too_large
+            );
+        }
+
+        let cases = [
+            ("0xffff_ffff_ffff_ffff", i64, Overflow::Wrapping, Ok(-1)),
+            ("0xff", i8, Overflow::Wrapping, Ok(-1)),
+            ("-0xff", i8, Overflow::Wrapping, Ok(1)),
+            (
+                "9223372036854775807",
+                i64,
+                Overflow::Checked,
+                Ok(i64::MAX as i128),
+            ),
+            (
+                "-9223372036854775808",
+                i64,
+                Overflow::Checked,
+                Ok(i64::MIN as i128),
+            ),
+            (
+                "0x7fff_ffff_ffff_ffff",
+                i64,
+                Overflow::Checked,
+                Ok(i64::MAX as i128),
+            ),
+            (
+                "-0o1_000_000_000_000_000_000_000",
+                i64,
+                Overflow::Checked,
+                Ok(i64::MIN as i128),
+            ),
+            ("255", u8, Overflow::Checked, Ok(255)),
+            ("127", i8, Overflow::Checked, Ok(127)),
+            ("-128", i8, Overflow::Checked, Ok(-128)),
+            (
+                "f32(1.5) >> 16",
+                Word::new(32.try_into().unwrap(), true),
+                Overflow::Checked,
+                Ok(0x3fc0),
+            ),
+            (
+                "0x1_0000_0000_0000_0000",
+                i64,
+                Overflow::Saturating,
+                Ok(i64::MAX as i128),
+            ),
+            ("300", u8, Overflow::Saturating, Ok(255)),
+            ("-300", i8, Overflow::Saturating, Ok(-127)),
+            ("1 + 0b1_0000_0000", u8, Overflow::Saturating, Ok(255)),
+        ];
+        for (line, word, overflow, expected) in cases {
+            assert_eq!(eval(line, word, overflow), expected, "{} in {}", line, word);
+        }
+
+        let overflows = [
+            ("9223372036854775808", i64, 0..19),
+            ("0x8000_0000_0000_0000", i64, 0..21),
+            ("0o1_000_000_000_000_000_000_000", i64, 0..31),
+            ("-0x8000_0000_0000_0001", i64, 1..22),
+            ("0x1_0000_0000_0000_0000", i64, 0..23),
+            ("1 + 0b1000_0000", i8, 4..15),
+            ("256", u8, 0..3),
+            ("-129", i8, 1..4),
+            ("ans[300]", i8, 4..7),
+            ("'é'", i8, 0..4),
+        ];
+        for (line, word, span) in overflows {
+            assert_eq!(
+                eval(line, word, Overflow::Checked),
+                Err(CorkError::LiteralOverflow { span, word }),
+                "{} in {}",
+                line,
+                word
+            );
+        }
+
+        // the spans of a function body are not of the line that calls it
+        let mut env = Env::new(i8, Overflow::Checked);
+        match parse_line("fn f(x) = x + 300").unwrap() {
+            Command::Define(function) => env.define(function).unwrap(),
+            _ => panic!("Should have parsed to a function"),
+        }
+        match parse_line("f(1)").unwrap() {
+            Command::Expr(expr) => assert_eq!(
+                eval_fixed(&expr, &env),
+                Err(CorkError::Eval(String::from(
+                    "literal overflows i8, it must be from -128 to 127, in the body of f"
+                )))
+            ),
+            _ => panic!("Should have parsed to an expr"),
+        }
+    }
 }
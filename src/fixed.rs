@@ -0,0 +1,204 @@
+use std::{fmt, str::FromStr};
+
+/// The most bits that a fixed-point number may have.
+pub const MAX_FIXED_BITS: u32 = 64;
+
+/// A QFormat is a signed fixed-point format with int_bits integer bits, counting the sign,
+/// followed by frac_bits fraction bits. Q15 is Q1.15, and Q16.16 is 32 bits wide.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QFormat {
+    int_bits: u32,
+    frac_bits: u32,
+}
+
+impl QFormat {
+    /// Returns the number of bits of a number of the format.
+    pub fn bits(self) -> u32 {
+        self.int_bits + self.frac_bits
+    }
+
+    /// encode returns the number written as the decimal literal, scaled by 2^frac_bits and
+    /// rounded to the nearest integer, halfway cases away from zero. It returns None if the
+    /// number does not fit in the format.
+    pub fn encode(self, literal: &str) -> Option<i128> {
+        let (negative, literal) = match literal.strip_prefix('-') {
+            Some(literal) => (true, literal),
+            None => (false, literal),
+        };
+        let (int, frac) = literal.split_once('.').unwrap_or((literal, ""));
+        let int: u128 = if int.is_empty() { 0 } else { int.parse().ok()? };
+        if int >> self.bits() != 0 {
+            return None;
+        }
+        // the digits past the 36th cannot change a fraction of at most 64 bits
+        let frac = &frac[..frac.len().min(36)];
+        let scale = 10u128.pow(frac.len() as u32);
+        let mut rest: u128 = if frac.is_empty() {
+            0
+        } else {
+            frac.parse().unwrap()
+        };
+        let mut frac_bits = 0;
+        for _ in 0..self.frac_bits {
+            rest *= 2;
+            frac_bits <<= 1;
+            if rest >= scale {
+                frac_bits |= 1;
+                rest -= scale;
+            }
+        }
+        if rest * 2 >= scale {
+            frac_bits += 1;
+        }
+        let magnitude = (int << self.frac_bits) + frac_bits;
+        let limit = 1 << (self.bits() - 1);
+        match negative {
+            true if magnitude <= limit => Some(-(magnitude as i128)),
+            false if magnitude < limit => Some(magnitude as i128),
+            _ => None,
+        }
+    }
+
+    /// decode formats the low bits of bits as a number of the format, in decimal. Every
+    /// number of the format has a finite decimal expansion, which is shown in full.
+    pub fn decode(self, bits: u128) -> String {
+        let shift = 128 - self.bits();
+        let num = ((bits << shift) as i128) >> shift;
+        let magnitude = num.unsigned_abs();
+        let mask = (1 << self.frac_bits) - 1;
+        let sign = if num < 0 { "-" } else { "" };
+        let mut decimal = format!("{}{}", sign, magnitude >> self.frac_bits);
+        let mut frac = magnitude & mask;
+        if frac != 0 {
+            decimal.push('.');
+        }
+        while frac != 0 {
+            frac *= 10;
+            decimal.push(char::from_digit((frac >> self.frac_bits) as u32, 10).unwrap());
+            frac &= mask;
+        }
+        decimal
+    }
+
+    /// Returns the least number of the format, in decimal.
+    pub fn min(self) -> String {
+        self.decode(1 << (self.bits() - 1))
+    }
+
+    /// Returns the greatest number of the format, in decimal.
+    pub fn max(self) -> String {
+        self.decode((1 << (self.bits() - 1)) - 1)
+    }
+
+    /// Returns the difference between two consecutive numbers of the format, in decimal.
+    pub fn step(self) -> String {
+        self.decode(1)
+    }
+}
+
+impl fmt::Display for QFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.int_bits == 1 {
+            write!(f, "q{}", self.frac_bits)
+        } else {
+            write!(f, "q{}.{}", self.int_bits, self.frac_bits)
+        }
+    }
+}
+
+impl FromStr for QFormat {
+    type Err = String;
+
+    /// Parses a format of the form "qN", which has a sign bit and N fraction bits,
+    /// or "qM.N", which has M integer bits and N fraction bits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("{} is not a valid fixed-point format", s);
+        let bits = s.strip_prefix('q').ok_or_else(invalid)?;
+        let (int_bits, frac_bits): (u32, _) = match bits.split_once('.') {
+            Some((m, n)) => (m.parse().map_err(|_| invalid())?, n),
+            None => (1, bits),
+        };
+        let frac_bits: u32 = frac_bits.parse().map_err(|_| invalid())?;
+        if int_bits == 0 || int_bits.saturating_add(frac_bits) > MAX_FIXED_BITS {
+            return Err(format!(
+                "{} is not a valid fixed-point format, it must have a sign bit and at most {} bits",
+                s, MAX_FIXED_BITS
+            ));
+        }
+        Ok(Self {
+            int_bits,
+            frac_bits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn q(s: &str) -> QFormat {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_q_format_parse() {
+        assert_eq!(
+            q("q15"),
+            QFormat {
+                int_bits: 1,
+                frac_bits: 15
+            }
+        );
+        assert_eq!(q("q16.16").bits(), 32);
+        assert_eq!(q("q1.31").to_string(), "q31");
+        assert_eq!(q("q8.0").to_string(), "q8.0");
+        for format in ["q", "q0.15", "q32.33", "q64", "Q15", "q1.", "x15", "q-1.5"] {
+            assert!(format.parse::<QFormat>().is_err(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_fixed_encode() {
+        let cases = [
+            ("q15", "0.5", Some(0x4000)),
+            ("q15", "-0.5", Some(-0x4000)),
+            ("q15", "-1", Some(-0x8000)),
+            ("q15", "1", None),
+            ("q15", "0.99997", Some(0x7fff)),
+            ("q15", "0.999999", None),
+            ("q15", ".1", Some(0x0ccd)),
+            ("q16.16", "1.5", Some(0x18000)),
+            ("q16.16", "-1.25", Some(-0x14000)),
+            ("q16.16", "32767.99999", Some(0x7fff_ffff)),
+            ("q16.16", "32768", None),
+            ("q8.0", "2.5", Some(3)),
+            ("q8.0", "-2.5", Some(-3)),
+            ("q31", "0.000000000232830643653869628906250000001", Some(1)),
+            ("q1.63", "-1", Some(i64::MIN as i128)),
+            ("q4.4", "99999999999999999999999999999999999999999", None),
+        ];
+        for (format, literal, num) in cases {
+            assert_eq!(q(format).encode(literal), num, "{}({})", format, literal);
+        }
+    }
+
+    #[test]
+    fn test_fixed_decode() {
+        let cases = [
+            ("q15", 0x4000, "0.5"),
+            ("q15", 0xc000, "-0.5"),
+            ("q15", 0x1_8000, "-1"),
+            ("q15", 1, "0.000030517578125"),
+            ("q16.16", 0x18000, "1.5"),
+            ("q16.16", 0xffff_ffff, "-0.0000152587890625"),
+            ("q8.0", 0xff, "-1"),
+            ("q1.63", 1 << 63, "-1"),
+        ];
+        for (format, bits, output) in cases {
+            assert_eq!(q(format).decode(bits), output, "{} {:#x}", format, bits);
+        }
+        assert_eq!(q("q15").min(), "-1");
+        assert_eq!(q("q15").max(), "0.999969482421875");
+        assert_eq!(q("q16.16").step(), "0.0000152587890625");
+    }
+}
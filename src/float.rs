@@ -0,0 +1,163 @@
+use std::{fmt, str::FromStr};
+
+use half::{bf16, f16};
+
+/// A FloatFormat is a binary floating-point format that the bits of a number can be read as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// IEEE-754 half precision.
+    F16,
+    /// bfloat16, a single precision number with the low 16 bits of its mantissa dropped.
+    Bf16,
+    /// IEEE-754 single precision.
+    F32,
+    /// IEEE-754 double precision.
+    F64,
+}
+
+impl FloatFormat {
+    /// Returns the number of bits of a number of the format.
+    pub fn bits(self) -> u32 {
+        match self {
+            Self::F16 | Self::Bf16 => 16,
+            Self::F32 => 32,
+            Self::F64 => 64,
+        }
+    }
+
+    /// Returns the number of bits of the exponent.
+    pub fn exponent_bits(self) -> u32 {
+        match self {
+            Self::F16 => 5,
+            Self::Bf16 | Self::F32 => 8,
+            Self::F64 => 11,
+        }
+    }
+
+    /// Returns the number of bits of the mantissa, which are the low bits of the number.
+    pub fn mantissa_bits(self) -> u32 {
+        self.bits() - self.exponent_bits() - 1
+    }
+
+    /// encode returns the bits of the number written as literal, rounded to the nearest number
+    /// of the format. It returns None if the number is too large to be represented.
+    pub fn encode(self, literal: &str) -> Option<u128> {
+        let (bits, infinite) = match self {
+            Self::F16 => {
+                let x = literal.parse::<f16>().ok()?;
+                (x.to_bits() as u128, x.is_infinite())
+            }
+            Self::Bf16 => {
+                let x = literal.parse::<bf16>().ok()?;
+                (x.to_bits() as u128, x.is_infinite())
+            }
+            Self::F32 => {
+                let x = literal.parse::<f32>().ok()?;
+                (x.to_bits() as u128, x.is_infinite())
+            }
+            Self::F64 => {
+                let x = literal.parse::<f64>().ok()?;
+                (x.to_bits() as u128, x.is_infinite())
+            }
+        };
+        let is_inf = literal.trim_start_matches('-').starts_with("inf");
+        if infinite && !is_inf {
+            return None;
+        }
+        Some(bits)
+    }
+
+    /// decode formats the low bits of bits as a number of the format, in the shortest form
+    /// that reads back as the same number.
+    pub fn decode(self, bits: u128) -> String {
+        match self {
+            Self::F16 => format!("{:?}", f16::from_bits(bits as u16)),
+            Self::Bf16 => format!("{:?}", bf16::from_bits(bits as u16)),
+            Self::F32 => format!("{:?}", f32::from_bits(bits as u32)),
+            Self::F64 => format!("{:?}", f64::from_bits(bits as u64)),
+        }
+    }
+}
+
+impl fmt::Display for FloatFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::F16 => "f16",
+            Self::Bf16 => "bf16",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for FloatFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "f16" => Ok(Self::F16),
+            "bf16" => Ok(Self::Bf16),
+            "f32" => Ok(Self::F32),
+            "f64" => Ok(Self::F64),
+            _ => Err(format!(
+                "{} is not a valid float format (expected f16, bf16, f32 or f64)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_float_encode() {
+        let cases = [
+            (FloatFormat::F32, "1.5", Some(0x3fc0_0000)),
+            (FloatFormat::F32, "-2.5", Some(0xc020_0000)),
+            (FloatFormat::F32, "0.1", Some(0x3dcc_cccd)),
+            (FloatFormat::F32, "1e39", None),
+            (FloatFormat::F32, "-inf", Some(0xff80_0000)),
+            (FloatFormat::F64, "1", Some(0x3ff0_0000_0000_0000)),
+            (FloatFormat::F64, "-0.0", Some(0x8000_0000_0000_0000)),
+            (FloatFormat::F64, "1e309", None),
+            (FloatFormat::F16, "1", Some(0x3c00)),
+            (FloatFormat::F16, "65504", Some(0x7bff)),
+            (FloatFormat::F16, "70000", None),
+            (FloatFormat::Bf16, "1", Some(0x3f80)),
+            (FloatFormat::Bf16, "-2.5", Some(0xc020)),
+        ];
+        for (format, literal, bits) in cases {
+            assert_eq!(format.encode(literal), bits, "{}({})", format, literal);
+        }
+    }
+
+    #[test]
+    fn test_float_decode() {
+        let cases = [
+            (FloatFormat::F32, 0x3f80_0000, "1.0"),
+            (FloatFormat::F32, 0xc020_0000, "-2.5"),
+            (FloatFormat::F32, 0x3dcc_cccd, "0.1"),
+            (FloatFormat::F32, 0x7f80_0000, "inf"),
+            (FloatFormat::F32, 0x7fc0_0000, "NaN"),
+            (FloatFormat::F32, 1, "1e-45"),
+            (FloatFormat::F64, 0x4009_21fb_5444_2d18, "3.141592653589793"),
+            (FloatFormat::F16, 0x3555, "0.33325195"),
+            (FloatFormat::Bf16, 0x3f80, "1.0"),
+            (FloatFormat::F32, 0xffff_3f80_0000, "1.0"),
+        ];
+        for (format, bits, output) in cases {
+            assert_eq!(format.decode(bits), output, "{} {:#x}", format, bits);
+        }
+    }
+
+    #[test]
+    fn test_float_format_parse() {
+        assert_eq!("bf16".parse(), Ok(FloatFormat::Bf16));
+        assert_eq!(FloatFormat::F64.to_string(), "f64");
+        assert_eq!(FloatFormat::F16.mantissa_bits(), 10);
+        assert!("f128".parse::<FloatFormat>().is_err());
+    }
+}
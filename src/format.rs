@@ -1,33 +1,111 @@
 use std::{
     char,
     fmt::{self, Display, Formatter},
+    str::FromStr,
 };
 
 use colored::*;
 use serde::Deserialize;
-use strum::EnumIter;
+use strum::{EnumIter, IntoEnumIterator};
 
-#[derive(EnumIter, Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+use crate::fixed::QFormat;
+use crate::float::FloatFormat;
+use crate::unit::{Dim, Unit};
+use crate::value::Value;
+use crate::word::{Width, Word};
+
+/// Base is a radix from 2 to 36, whose digits are 0-9 followed by a-z.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "u32")]
+pub struct Base(u32);
+
+impl Base {
+    pub fn radix(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for Base {
+    fn default() -> Self {
+        Self(10)
+    }
+}
+
+impl TryFrom<u32> for Base {
+    type Error = String;
+
+    fn try_from(radix: u32) -> Result<Self, Self::Error> {
+        match radix {
+            2..=36 => Ok(Self(radix)),
+            _ => Err(format!("{} is not a valid base (expected 2 to 36)", radix)),
+        }
+    }
+}
+
+impl FromStr for Base {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let radix = s
+            .parse::<u32>()
+            .map_err(|_| format!("{} is not a valid base", s))?;
+        Self::try_from(radix)
+    }
+}
+
+#[derive(EnumIter, Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
 pub enum FormatRadix {
     Decimal,
+    #[default]
     Hex,
     Octal,
     Binary,
+    /// Any other base, written as base(N).
+    #[strum(disabled)]
+    Base(Base),
 }
 
 impl FormatRadix {
-    fn fmt_uint_to_chars(&self, num: u64) -> Vec<char> {
-        let mut rev_chars = match self {
-            FormatRadix::Decimal => uint_to_chars_radix(num, 10),
-            FormatRadix::Hex => uint_to_chars_radix(num, 16),
-            FormatRadix::Octal => uint_to_chars_radix(num, 8),
-            FormatRadix::Binary => uint_to_chars_radix(num, 2),
-        };
+    fn fmt_uint_to_chars(&self, num: u128) -> Vec<char> {
+        let mut rev_chars = uint_to_chars_radix(num, u32::from(*self));
         rev_chars.reverse();
         rev_chars
     }
 }
 
+impl From<Base> for FormatRadix {
+    /// Converts the base to the named radix of the same value, if there is one.
+    fn from(base: Base) -> Self {
+        match base.radix() {
+            10 => FormatRadix::Decimal,
+            16 => FormatRadix::Hex,
+            8 => FormatRadix::Octal,
+            2 => FormatRadix::Binary,
+            _ => FormatRadix::Base(base),
+        }
+    }
+}
+
+impl FromStr for FormatRadix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dec" => Ok(Self::Decimal),
+            "hex" => Ok(Self::Hex),
+            "oct" => Ok(Self::Octal),
+            "bin" => Ok(Self::Binary),
+            _ => match s.strip_prefix("base(").and_then(|s| s.strip_suffix(')')) {
+                Some(radix) => Ok(radix.parse::<Base>()?.into()),
+                None => Err(format!(
+                    "{} is not a valid radix (expected dec, hex, oct, bin or base(N))",
+                    s
+                )),
+            },
+        }
+    }
+}
+
 impl From<FormatRadix> for u32 {
     fn from(val: FormatRadix) -> Self {
         match val {
@@ -35,57 +113,526 @@ impl From<FormatRadix> for u32 {
             FormatRadix::Hex => 16,
             FormatRadix::Octal => 8,
             FormatRadix::Binary => 2,
+            FormatRadix::Base(base) => base.radix(),
+        }
+    }
+}
+
+impl FormatRadix {
+    fn label(&self) -> ColoredString {
+        match self {
+            FormatRadix::Decimal => "Decimal".green(),
+            FormatRadix::Hex => "Hexadecimal".yellow(),
+            FormatRadix::Octal => "Octal".blue(),
+            FormatRadix::Binary => "Binary".magenta(),
+            FormatRadix::Base(base) => format!("Base {}", base.radix()).cyan(),
         }
     }
 }
 
 impl Display for FormatRadix {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/// A Target is what the result of "expr to target" is shown as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Radix(FormatRadix),
+    /// A table of the number in every radix.
+    All,
+    /// The bits of the number, one by one.
+    Bits(BitOrder),
+    /// The bytes of the number in hex, as a hexdump shows them.
+    Bytes(Endian),
+    /// The low bits of the number read as a floating-point number, field by field.
+    Float(FloatFormat),
+    /// The low bits of the number read as a fixed-point number, in decimal.
+    Fixed(QFormat),
+    /// The number as a size in bytes, in the largest unit it reaches.
+    Size,
+    /// The number of nanoseconds or hertz as a quantity of the unit.
+    Unit(Unit),
+    /// The number as a count of ticks.
+    Ticks,
+    /// The bytes of the number as ASCII characters.
+    Ascii,
+    /// The bytes of the number as UTF-8 text.
+    Str,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(' ') {
+            Some(("bits", order)) => Ok(Self::Bits(order.parse()?)),
+            Some(("bytes", endian)) => Ok(Self::Bytes(endian.parse()?)),
+            _ => match s {
+                "all" => Ok(Self::All),
+                "ascii" => Ok(Self::Ascii),
+                "str" => Ok(Self::Str),
+                "bits" => Ok(Self::Bits(BitOrder::default())),
+                "bytes" => Ok(Self::Bytes(Endian::default())),
+                "size" => Ok(Self::Size),
+                "ticks" => Ok(Self::Ticks),
+                _ if s.starts_with('q') => Ok(Self::Fixed(s.parse()?)),
+                _ if s.parse::<Unit>().is_ok() => Ok(Self::Unit(s.parse()?)),
+                _ => match s.parse() {
+                    Ok(float) => Ok(Self::Float(float)),
+                    Err(_) => Ok(Self::Radix(s.parse()?)),
+                },
+            },
+        }
+    }
+}
+
+/// BitOrder is the order in which the bits of a number are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit first, as numbers are written.
+    #[default]
+    Msb,
+    /// The least significant bit first, as bit indices count.
+    Lsb,
+}
+
+impl FromStr for BitOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "msb" => Ok(Self::Msb),
+            "lsb" => Ok(Self::Lsb),
+            _ => Err(format!(
+                "{} is not a valid bit order (expected msb or lsb)",
+                s
+            )),
+        }
+    }
+}
+
+/// Endian is the order in which the bytes of a number are laid out in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Endian {
+    /// The most significant byte first.
+    #[default]
+    Big,
+    /// The least significant byte first.
+    Little,
+}
+
+impl FromStr for Endian {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "be" => Ok(Self::Big),
+            "le" => Ok(Self::Little),
+            _ => Err(format!(
+                "{} is not a valid byte order (expected be or le)",
+                s
+            )),
+        }
+    }
+}
+
+/// A Row is an extra row of the table shown for the All target.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum Row {
+    /// The number as a signed decimal of the word.
+    Signed,
+    /// The number as an unsigned decimal of the word.
+    Unsigned,
+    /// The bytes of the number as ASCII characters.
+    Ascii,
+    /// The index of every fourth bit, under the binary row.
+    Ruler,
+}
+
+impl FromStr for Row {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "signed" => Ok(Self::Signed),
+            "unsigned" => Ok(Self::Unsigned),
+            "ascii" => Ok(Self::Ascii),
+            "ruler" => Ok(Self::Ruler),
+            _ => Err(format!(
+                "{} is not a valid row (expected signed, unsigned, ascii or ruler)",
+                s
+            )),
+        }
+    }
+}
+
+/// The width of the labels in the table of the All target.
+const LABEL_WIDTH: usize = 11;
+
+/// Negative is how negative numbers are shown in the hexadecimal, octal and binary radices.
+/// Decimal numbers always have a sign.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum Negative {
+    /// A minus sign followed by the magnitude, as in -0x10.
+    #[default]
+    Sign,
+    /// The two's complement bit-pattern of the word, as in 0xfffffff0.
+    Twos,
+}
+
+impl FromStr for Negative {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sign" => Ok(Self::Sign),
+            "twos" => Ok(Self::Twos),
+            _ => Err(format!(
+                "{} is not a valid negative style (expected sign or twos)",
+                s
+            )),
+        }
+    }
+}
+
+/// Case is the letter case of the digits above 9.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum Case {
+    #[default]
+    Lower,
+    Upper,
+}
+
+impl FromStr for Case {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lower" => Ok(Self::Lower),
+            "upper" => Ok(Self::Upper),
+            _ => Err(format!(
+                "{} is not a valid case (expected lower or upper)",
+                s
+            )),
+        }
+    }
+}
+
+/// Prefix is the style in which the radix of a non-decimal number is marked.
+/// Decimal numbers are never marked, and other bases are marked as 36#zz in all styles but Ada.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum Prefix {
+    /// 0xcafe, 0o17, 0b101
+    #[default]
+    C,
+    /// 0cafeh, 17o, 101b
+    Intel,
+    /// $cafe, @17, %101
+    Motorola,
+    /// 16#cafe#, 8#17#, 2#101#
+    Ada,
+    /// cafe, 17, 101
+    None,
+}
+
+impl FromStr for Prefix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "c" => Ok(Self::C),
+            "intel" => Ok(Self::Intel),
+            "motorola" => Ok(Self::Motorola),
+            "ada" => Ok(Self::Ada),
+            "none" => Ok(Self::None),
+            _ => Err(format!(
+                "{} is not a valid prefix style (expected c, intel, motorola, ada or none)",
+                s
+            )),
+        }
+    }
+}
+
+impl Prefix {
+    /// Returns the prefix and suffix that mark digits as a number of radix.
+    fn affixes(&self, radix: FormatRadix, digits: &str) -> (String, String) {
+        let (prefix, suffix) = match (self, radix) {
+            (Prefix::Ada, FormatRadix::Base(base)) => {
+                return (format!("{}#", base.radix()), String::from("#"))
+            }
+            // the digits alone would not tell which base they are in, whatever the style
+            (_, FormatRadix::Base(base)) => return (format!("{}#", base.radix()), String::new()),
+            (_, FormatRadix::Decimal) | (Prefix::None, _) => ("", ""),
+            (Prefix::C, FormatRadix::Hex) => ("0x", ""),
+            (Prefix::C, FormatRadix::Octal) => ("0o", ""),
+            (Prefix::C, FormatRadix::Binary) => ("0b", ""),
+            // Intel hex numbers have to start with a digit, so as to not be read as names
+            (Prefix::Intel, FormatRadix::Hex) if digits.starts_with(char::is_alphabetic) => {
+                ("0", "h")
+            }
+            (Prefix::Intel, FormatRadix::Hex) => ("", "h"),
+            (Prefix::Intel, FormatRadix::Octal) => ("", "o"),
+            (Prefix::Intel, FormatRadix::Binary) => ("", "b"),
+            (Prefix::Motorola, FormatRadix::Hex) => ("$", ""),
+            (Prefix::Motorola, FormatRadix::Octal) => ("@", ""),
+            (Prefix::Motorola, FormatRadix::Binary) => ("%", ""),
+            (Prefix::Ada, FormatRadix::Hex) => ("16#", "#"),
+            (Prefix::Ada, FormatRadix::Octal) => ("8#", "#"),
+            (Prefix::Ada, FormatRadix::Binary) => ("2#", "#"),
+        };
+        (prefix.to_string(), suffix.to_string())
+    }
+}
+
+/// The letters of the size prefixes, each standing for a multiple of the one before it.
+pub const SIZE_PREFIXES: [char; 6] = ['K', 'M', 'G', 'T', 'P', 'E'];
+
+/// SizePrefixes are the multiples of a byte that K, M, G and the other size prefixes stand for.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum SizePrefixes {
+    /// Powers of 1024, as in KiB.
+    #[default]
+    Binary,
+    /// Powers of 1000, as in kB.
+    #[serde(alias = "SI")]
+    Si,
+}
+
+impl SizePrefixes {
+    /// Returns the number of bytes that the prefix with the given power stands for, such as
+    /// 2 for M.
+    pub fn multiple(self, power: u32) -> u128 {
         match self {
-            FormatRadix::Decimal => write!(f, "{}", "Decimal".green()),
-            FormatRadix::Hex => write!(f, "{}", "Hexadecimal".yellow()),
-            FormatRadix::Octal => write!(f, "{}", "Octal".blue()),
-            FormatRadix::Binary => write!(f, "{}", "Binary".magenta()),
+            Self::Binary => 1 << (10 * power),
+            Self::Si => 1000u128.pow(power),
+        }
+    }
+
+    /// Returns the unit of the multiple with the given power, from B for bytes upwards.
+    fn unit(self, power: u32) -> String {
+        match (self, power) {
+            (_, 0) => String::from("B"),
+            (Self::Binary, _) => format!("{}iB", SIZE_PREFIXES[power as usize - 1]),
+            (Self::Si, 1) => String::from("kB"),
+            (Self::Si, _) => format!("{}B", SIZE_PREFIXES[power as usize - 1]),
+        }
+    }
+}
+
+impl FromStr for SizePrefixes {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" => Ok(Self::Binary),
+            "si" => Ok(Self::Si),
+            _ => Err(format!(
+                "{} is not a valid kind of size prefixes (expected binary or si)",
+                s
+            )),
+        }
+    }
+}
+
+/// Pad is how many digits output numbers are padded to with leading zeros.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(from = "PadSetting")]
+pub enum Pad {
+    #[default]
+    Off,
+    /// As many digits as the largest number of the word has.
+    Word,
+    Digits(usize),
+}
+
+/// PadSetting is how Pad is written in the config, either as on/off or as a number of digits.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PadSetting {
+    Switch(bool),
+    Digits(usize),
+}
+
+impl From<PadSetting> for Pad {
+    fn from(setting: PadSetting) -> Self {
+        match setting {
+            PadSetting::Switch(true) => Pad::Word,
+            PadSetting::Switch(false) | PadSetting::Digits(0) => Pad::Off,
+            PadSetting::Digits(digits) => Pad::Digits(digits),
         }
     }
 }
 
-fn uint_to_chars_radix(mut num: u64, radix: u32) -> Vec<char> {
+impl FromStr for Pad {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let setting = match s {
+            "on" | "true" => PadSetting::Switch(true),
+            "off" | "false" => PadSetting::Switch(false),
+            _ => PadSetting::Digits(s.parse().map_err(|_| {
+                format!(
+                    "{} is not a valid padding (expected on, off or a number of digits)",
+                    s
+                )
+            })?),
+        };
+        Ok(setting.into())
+    }
+}
+
+fn uint_to_chars_radix(mut num: u128, radix: u32) -> Vec<char> {
     let mut chars = Vec::new();
     if num == 0 {
         chars.push('0');
     }
     while num > 0 {
-        let d = (num % radix as u64) as u32;
+        let d = (num % radix as u128) as u32;
         chars.push(char::from_digit(d, radix).unwrap());
-        num /= radix as u64;
+        num /= radix as u128;
     }
     chars
 }
 
-fn uint_with_separators(chars: &[char], radix: FormatRadix) -> String {
-    let interval = match radix {
-        FormatRadix::Decimal | FormatRadix::Octal => 3,
-        FormatRadix::Hex | FormatRadix::Binary => 4,
-    };
+/// A Separator is the character placed between groups of digits in punctuated numbers.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "char")]
+pub struct Separator(char);
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self('_')
+    }
+}
+
+impl TryFrom<char> for Separator {
+    type Error = String;
+
+    fn try_from(sep: char) -> Result<Self, Self::Error> {
+        match sep {
+            '_' | ',' | '\'' | ' ' => Ok(Self(sep)),
+            _ => Err(format!(
+                "{:?} is not a valid separator (expected _, comma, ' or space)",
+                sep
+            )),
+        }
+    }
+}
+
+impl FromStr for Separator {
+    type Err = String;
+
+    /// Parses a separator, where "space" stands for the space character.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (s, chars.next(), chars.next()) {
+            ("space", _, _) => Ok(Self(' ')),
+            (_, Some(sep), None) => sep.try_into(),
+            _ => Err(format!("{} is not a valid separator", s)),
+        }
+    }
+}
+
+/// GroupSizes is the number of digits in each group of a punctuated number, per radix.
+/// A size of 0 leaves the digits of that radix ungrouped. Bases other than
+/// decimal, hex, octal and binary share a single size.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GroupSizes {
+    decimal: usize,
+    hex: usize,
+    octal: usize,
+    binary: usize,
+    other: usize,
+}
+
+impl Default for GroupSizes {
+    fn default() -> Self {
+        Self {
+            decimal: 3,
+            hex: 4,
+            octal: 3,
+            binary: 4,
+            other: 4,
+        }
+    }
+}
+
+impl GroupSizes {
+    pub fn get(&self, radix: FormatRadix) -> usize {
+        match radix {
+            FormatRadix::Decimal => self.decimal,
+            FormatRadix::Hex => self.hex,
+            FormatRadix::Octal => self.octal,
+            FormatRadix::Binary => self.binary,
+            FormatRadix::Base(_) => self.other,
+        }
+    }
+
+    pub fn set(&mut self, radix: FormatRadix, size: usize) {
+        match radix {
+            FormatRadix::Decimal => self.decimal = size,
+            FormatRadix::Hex => self.hex = size,
+            FormatRadix::Octal => self.octal = size,
+            FormatRadix::Binary => self.binary = size,
+            FormatRadix::Base(_) => self.other = size,
+        }
+    }
+}
+
+/// A GroupSize is the group size of a single radix, written as "radix=size".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GroupSize {
+    pub radix: FormatRadix,
+    pub size: usize,
+}
+
+impl FromStr for GroupSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((radix, size)) = s.split_once('=') else {
+            return Err(format!("{} is not of the form radix=size", s));
+        };
+        let size = size
+            .parse()
+            .map_err(|_| format!("{} is not a valid group size", size))?;
+        Ok(Self {
+            radix: radix.parse()?,
+            size,
+        })
+    }
+}
+
+fn uint_with_separators(chars: &[char], sep: Separator, interval: usize) -> String {
+    if interval == 0 {
+        return String::from_iter(chars);
+    }
     let chunks: Vec<_> = chars
         .rchunks(interval)
         .map(String::from_iter)
         .rev()
         .collect();
-    chunks.join("_")
-}
-
-impl Default for FormatRadix {
-    fn default() -> Self {
-        Self::Hex
-    }
+    chunks.join(&sep.0.to_string())
 }
 
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct OutputFormat {
     radix: FormatRadix,
     punctuate_number: bool,
+    separator: Separator,
+    group_sizes: GroupSizes,
+    word: Word,
+    negative: Negative,
+    pad: Pad,
+    case: Case,
+    prefix: Prefix,
+    size_prefixes: SizePrefixes,
+    /// What answers are shown as, if not a number of the radix.
+    target: Option<Target>,
+    rows: Vec<Row>,
 }
 
 impl OutputFormat {
@@ -99,41 +646,515 @@ impl OutputFormat {
         self
     }
 
+    pub fn with_separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    pub fn with_group_sizes(mut self, group_sizes: GroupSizes) -> Self {
+        self.group_sizes = group_sizes;
+        self
+    }
+
+    pub fn with_word(mut self, word: Word) -> Self {
+        self.word = word;
+        self
+    }
+
+    pub fn with_negative(mut self, negative: Negative) -> Self {
+        self.negative = negative;
+        self
+    }
+
+    pub fn with_pad(mut self, pad: Pad) -> Self {
+        self.pad = pad;
+        self
+    }
+
+    pub fn with_case(mut self, case: Case) -> Self {
+        self.case = case;
+        self
+    }
+
+    pub fn with_prefix(mut self, prefix: Prefix) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn with_size_prefixes(mut self, size_prefixes: SizePrefixes) -> Self {
+        self.size_prefixes = size_prefixes;
+        self
+    }
+
+    pub fn with_rows(mut self, rows: Vec<Row>) -> Self {
+        self.rows = rows;
+        self
+    }
+
     pub fn set_format_radix(&mut self, radix: FormatRadix) {
         self.radix = radix;
     }
 
-    pub fn punctuate_number(&self) -> bool {
-        self.punctuate_number
+    pub fn set_punctuate_number(&mut self, punctuate_number: bool) {
+        self.punctuate_number = punctuate_number;
+    }
+
+    pub fn set_separator(&mut self, separator: Separator) {
+        self.separator = separator;
+    }
+
+    pub fn set_group_size(&mut self, radix: FormatRadix, size: usize) {
+        self.group_sizes.set(radix, size);
     }
 
-    pub fn fmt(&self, num: i64) -> String {
-        let (abs_num, negative) = if num < 0 {
-            (-num as u64, true)
+    pub fn set_word(&mut self, word: Word) {
+        self.word = word;
+    }
+
+    pub fn set_negative(&mut self, negative: Negative) {
+        self.negative = negative;
+    }
+
+    pub fn set_pad(&mut self, pad: Pad) {
+        self.pad = pad;
+    }
+
+    pub fn set_case(&mut self, case: Case) {
+        self.case = case;
+    }
+
+    pub fn set_prefix(&mut self, prefix: Prefix) {
+        self.prefix = prefix;
+    }
+
+    pub fn set_size_prefixes(&mut self, size_prefixes: SizePrefixes) {
+        self.size_prefixes = size_prefixes;
+    }
+
+    pub fn set_target(&mut self, target: Option<Target>) {
+        self.target = target;
+    }
+
+    pub fn set_rows(&mut self, rows: Vec<Row>) {
+        self.rows = rows;
+    }
+
+    /// Returns the number of digits in the largest magnitude of the word.
+    fn word_digits(&self) -> usize {
+        let largest = if self.word.signed() && self.radix == FormatRadix::Decimal {
+            self.word.min().unsigned_abs()
         } else {
-            (num as u64, false)
+            self.word.bits_of(-1)
+        };
+        self.radix.fmt_uint_to_chars(largest).len()
+    }
+
+    /// fmt formats a fixed value as a value of the word, that is, truncated to its width
+    /// and negative only if the word is signed. A big value is formatted in full.
+    /// Negative fixed values are shown as their bit-pattern if negative is Twos,
+    /// except in decimal. Padding to the word only applies to fixed values.
+    pub fn fmt(&self, value: &Value) -> String {
+        let twos = self.negative == Negative::Twos && self.radix != FormatRadix::Decimal;
+        let (mut abs_num_chars, negative) = match value {
+            Value::Fixed(num) => {
+                let num = self.word.wrap(*num);
+                if twos {
+                    (self.radix.fmt_uint_to_chars(self.word.bits_of(num)), false)
+                } else if self.word.signed() && num < 0 {
+                    (self.radix.fmt_uint_to_chars(num.unsigned_abs()), true)
+                } else {
+                    (self.radix.fmt_uint_to_chars(num as u128), false)
+                }
+            }
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => {
+                let abs_num_str = num.magnitude().to_str_radix(self.radix.into());
+                (
+                    abs_num_str.chars().collect(),
+                    num.sign() == num_bigint::Sign::Minus,
+                )
+            }
+        };
+        let digits = match (self.pad, value) {
+            (Pad::Off, _) => 0,
+            (Pad::Word, Value::Fixed(_)) => self.word_digits(),
+            #[cfg(feature = "bignum")]
+            (Pad::Word, Value::Big(_)) => 0,
+            (Pad::Digits(digits), _) => digits,
         };
-        let abs_num_chars = self.radix.fmt_uint_to_chars(abs_num);
+        if abs_num_chars.len() < digits {
+            let zeros = digits - abs_num_chars.len();
+            abs_num_chars.splice(0..0, std::iter::repeat_n('0', zeros));
+        }
+        if self.case == Case::Upper {
+            abs_num_chars
+                .iter_mut()
+                .for_each(char::make_ascii_uppercase);
+        }
         let abs_num_str = if self.punctuate_number {
-            uint_with_separators(&abs_num_chars, self.radix)
+            uint_with_separators(
+                &abs_num_chars,
+                self.separator,
+                self.group_sizes.get(self.radix),
+            )
         } else {
             String::from_iter(&abs_num_chars)
         };
-        let prefix = match self.radix {
-            FormatRadix::Decimal => "",
-            FormatRadix::Hex => "0x",
-            FormatRadix::Octal => "0o",
-            FormatRadix::Binary => "0b",
+        let (prefix, suffix) = self.prefix.affixes(self.radix, &abs_num_str);
+        let sign = if negative { "-" } else { "" };
+        format!("{}{}{}{}", sign, prefix, abs_num_str, suffix)
+    }
+
+    /// Formats value as the answer of a command, as the target if one is set.
+    pub fn show(&self, value: &Value) -> String {
+        match self.target {
+            Some(target) => self.fmt_to(target, value),
+            None => self.fmt(value),
+        }
+    }
+
+    /// Formats value as target, keeping the rest of the format.
+    pub fn fmt_to(&self, target: Target, value: &Value) -> String {
+        match target {
+            Target::Radix(radix) => self.clone().with_format_radix(radix).fmt(value),
+            Target::All => self.fmt_all(value),
+            Target::Bits(order) => self.fmt_bits(value, order),
+            Target::Bytes(endian) => self.fmt_bytes(value, endian),
+            Target::Float(float) => self.fmt_float(value, float),
+            Target::Fixed(q) => q.decode(self.extended_bits(value)),
+            Target::Size => self.fmt_size(value),
+            Target::Unit(unit) => self.fmt_quantity(value, unit),
+            Target::Ticks => {
+                let decimal = self.clone().with_format_radix(FormatRadix::Decimal);
+                format!("{} ticks", decimal.fmt(value))
+            }
+            Target::Ascii => self.fmt_text(value, false),
+            Target::Str => self.fmt_text(value, true),
+        }
+    }
+
+    /// fmt_all formats value as a table with a labelled row per radix, including the radix of
+    /// the format if it is another base. The extra rows follow, except for the ruler,
+    /// which goes right under the binary row.
+    pub fn fmt_all(&self, value: &Value) -> String {
+        let mut radices: Vec<_> = FormatRadix::iter().collect();
+        if let FormatRadix::Base(_) = self.radix {
+            radices.insert(radices.len() - 1, self.radix);
+        }
+        let mut rows: Vec<_> = radices
+            .into_iter()
+            .map(|radix| (radix.label(), self.fmt_to(Target::Radix(radix), value)))
+            .collect();
+        if self.rows.contains(&Row::Ruler) {
+            rows.push(("".normal(), self.fmt_ruler(value)));
+        }
+        for row in &self.rows {
+            let mut word = self.word;
+            let decimal = self.clone().with_format_radix(FormatRadix::Decimal);
+            match row {
+                Row::Signed => {
+                    word.set_signed(true);
+                    rows.push(("Signed".green(), decimal.with_word(word).fmt(value)));
+                }
+                Row::Unsigned => {
+                    word.set_signed(false);
+                    rows.push(("Unsigned".green(), decimal.with_word(word).fmt(value)));
+                }
+                Row::Ascii => rows.push(("ASCII".red(), self.fmt_text(value, false))),
+                Row::Ruler => {}
+            }
+        }
+        let lines: Vec<_> = rows
+            .into_iter()
+            .map(|(label, num)| {
+                let indent = " ".repeat(LABEL_WIDTH.saturating_sub(label.len()));
+                let colon = if label.is_empty() { " " } else { ":" };
+                format!("{}{}{} {}", indent, label, colon, num)
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Returns a ruler with the index of every fourth bit, aligned under the binary form of value.
+    /// An index is left out if it would run into the one to its right.
+    fn fmt_ruler(&self, value: &Value) -> String {
+        let bare = self
+            .clone()
+            .with_format_radix(FormatRadix::Binary)
+            .with_prefix(Prefix::None)
+            .fmt(value);
+        let (sign, digits) = match bare.strip_prefix('-') {
+            Some(digits) => (1, digits),
+            None => (0, bare.as_str()),
+        };
+        let (prefix, _) = self.prefix.affixes(FormatRadix::Binary, digits);
+        let indent = sign + prefix.len();
+        let mut ruler = vec![' '; indent + digits.len()];
+        // the column at which the next index must end before
+        let mut free = ruler.len();
+        let mut bit = 0;
+        for (col, c) in digits.char_indices().rev() {
+            if c != '0' && c != '1' {
+                continue;
+            }
+            let col = indent + col;
+            let index = bit.to_string();
+            if bit % 4 == 0 && col < free && col + 1 >= index.len() {
+                let start = col + 1 - index.len();
+                ruler.splice(start..=col, index.chars());
+                free = start.saturating_sub(1);
+            }
+            bit += 1;
+        }
+        String::from_iter(ruler).trim_end().to_string()
+    }
+
+    /// Returns the bytes of value, most significant first: as many as the word has for a fixed
+    /// value, and as few as hold the two's complement form of a big value.
+    fn bytes(&self, value: &Value) -> Vec<u8> {
+        match value {
+            Value::Fixed(num) => {
+                let bytes = self.word.bits_of(*num).to_be_bytes();
+                bytes[16 - self.word.bits() as usize / 8..].to_vec()
+            }
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => num.to_signed_bytes_be(),
+        }
+    }
+
+    /// Returns the low 128 bits of value, as they are laid out by bytes.
+    fn low_bits(&self, value: &Value) -> u128 {
+        self.bytes(value)
+            .iter()
+            .fold(0, |bits, &byte| (bits << 8) | byte as u128)
+    }
+
+    /// Returns the low 128 bits of value, with the sign of a negative value extended to them
+    /// if the word is signed or the value is big.
+    fn extended_bits(&self, value: &Value) -> u128 {
+        match value {
+            Value::Fixed(num) if self.word.signed() => self.word.wrap(*num) as u128,
+            Value::Fixed(num) => self.word.bits_of(*num),
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => {
+                let low = num & num_bigint::BigInt::from(u128::MAX);
+                u128::try_from(low).unwrap()
+            }
+        }
+    }
+
+    /// fmt_bits lays out the bits of value in rows of 32, in order, with the index of the
+    /// first bit of each nibble above it. Nibbles are spaced apart, bytes more so,
+    /// and set bits are highlighted.
+    pub fn fmt_bits(&self, value: &Value, order: BitOrder) -> String {
+        let bytes = self.bytes(value);
+        let bit = |index: usize| (bytes[bytes.len() - 1 - index / 8] >> (index % 8)) & 1 == 1;
+        let mut indices: Vec<_> = (0..bytes.len() * 8).collect();
+        if order == BitOrder::Msb {
+            indices.reverse();
+        }
+        // a nibble is 4 bits wide, unless its index is wider
+        let width = (indices.len() - 1).to_string().len().max(4);
+        let mut lines = Vec::new();
+        for row in indices.chunks(32) {
+            let mut ruler = String::new();
+            let mut bits = String::new();
+            for (i, nibble) in row.chunks(4).enumerate() {
+                if i > 0 {
+                    let gap = if i % 2 == 0 { "  " } else { " " };
+                    ruler.push_str(gap);
+                    bits.push_str(gap);
+                }
+                ruler.push_str(&format!("{:<width$}", nibble[0]));
+                for &index in nibble {
+                    let digit = if bit(index) {
+                        "1".green().bold()
+                    } else {
+                        "0".dimmed()
+                    };
+                    bits.push_str(&digit.to_string());
+                }
+                bits.push_str(&" ".repeat(width - 4));
+            }
+            lines.push(ruler.trim_end().to_string());
+            lines.push(bits.trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// fmt_bytes formats the bytes of value as two hex digits each, separated by spaces and
+    /// laid out in the given byte order.
+    fn fmt_bytes(&self, value: &Value, endian: Endian) -> String {
+        let mut bytes = self.bytes(value);
+        if endian == Endian::Little {
+            bytes.reverse();
+        }
+        let bytes: Vec<_> = bytes
+            .iter()
+            .map(|byte| match self.case {
+                Case::Lower => format!("{:02x}", byte),
+                Case::Upper => format!("{:02X}", byte),
+            })
+            .collect();
+        bytes.join(" ")
+    }
+
+    /// fmt_float reads the low bits of value as a number of the float format, and formats it
+    /// followed by its sign, exponent and mantissa, one per line as "name[hi:lo] = field".
+    fn fmt_float(&self, value: &Value, float: FloatFormat) -> String {
+        let bits = self.low_bits(value);
+        let word = Word::new(Width::try_from(float.bits()).unwrap(), false);
+        let of = self.clone().with_word(word);
+        let mantissa = float.mantissa_bits();
+        let sign = float.bits() - 1;
+        let field = |hi: u32, lo: u32| (bits >> lo) & (u128::MAX >> (127 - (hi - lo)));
+        let fields = [
+            (format!("sign[{}]", sign), field(sign, sign)),
+            (
+                format!("exponent[{}:{}]", sign - 1, mantissa),
+                field(sign - 1, mantissa),
+            ),
+            (
+                format!("mantissa[{}:0]", mantissa - 1),
+                field(mantissa - 1, 0),
+            ),
+        ];
+        let width = fields.iter().map(|(label, _)| label.len()).max().unwrap();
+        let mut lines = vec![float.decode(bits)];
+        for (label, field) in fields {
+            let field = of.fmt(&Value::Fixed(field as i128));
+            lines.push(format!("{:<width$} = {}", label, field));
+        }
+        lines.join("\n")
+    }
+
+    /// fmt_size formats value as a number of bytes in the largest unit that it reaches, with up
+    /// to two decimals. A size that had to be rounded is marked with a "~" after its sign.
+    /// A fixed value is read as a value of the word, as in fmt.
+    fn fmt_size(&self, value: &Value) -> String {
+        let Some((magnitude, negative)) = self.sign_magnitude(value) else {
+            let bytes = self.clone().with_format_radix(FormatRadix::Decimal);
+            return format!("{} B", bytes.fmt(value));
+        };
+        let prefixes = self.size_prefixes;
+        let power = (1..=SIZE_PREFIXES.len() as u32)
+            .rev()
+            .find(|&power| magnitude >= prefixes.multiple(power))
+            .unwrap_or(0);
+        let multiple = prefixes.multiple(power);
+        let (mut whole, rest) = (magnitude / multiple, magnitude % multiple);
+        let mut hundredths = (rest * 100 + multiple / 2) / multiple;
+        if hundredths == 100 {
+            whole += 1;
+            hundredths = 0;
+        }
+        let approx = if rest * 100 % multiple != 0 { "~" } else { "" };
+        let sign = if negative { "-" } else { "" };
+        let mut size = format!("{}{}{}", sign, approx, whole);
+        if hundredths != 0 {
+            size.push_str(format!(".{:02}", hundredths).trim_end_matches('0'));
+        }
+        format!("{} {}", size, prefixes.unit(power))
+    }
+
+    /// fmt_quantity formats value, a number of nanoseconds or hertz, in the unit. A big value
+    /// too large for that is shown in nanoseconds or hertz.
+    fn fmt_quantity(&self, value: &Value, unit: Unit) -> String {
+        let Some((magnitude, negative)) = self.sign_magnitude(value) else {
+            let num = self
+                .clone()
+                .with_format_radix(FormatRadix::Decimal)
+                .fmt(value);
+            let base = if unit.dim() == Dim::Time {
+                Unit::Ns
+            } else {
+                Unit::Hz
+            };
+            return format!("{} {}", num, base);
         };
+        unit.fmt_quantity(magnitude, negative)
+    }
+
+    /// Returns the magnitude of value and whether it is negative, reading a fixed value as a
+    /// value of the word, as fmt does. A big value whose magnitude does not fit in a u128 gives
+    /// None.
+    fn sign_magnitude(&self, value: &Value) -> Option<(u128, bool)> {
+        match value {
+            Value::Fixed(num) => {
+                let num = self.word.wrap(*num);
+                if self.word.signed() && num < 0 {
+                    Some((num.unsigned_abs(), true))
+                } else {
+                    Some((self.word.bits_of(num), false))
+                }
+            }
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => {
+                let magnitude = u128::try_from(num.magnitude()).ok()?;
+                Some((magnitude, num.sign() == num_bigint::Sign::Minus))
+            }
+        }
+    }
 
-        if negative {
-            format!("-{}{}", prefix, abs_num_str)
+    /// fmt_text formats the bytes of value as a character literal, from the most significant byte
+    /// that is not zero. Bytes that are not printable ASCII are escaped, except for printable
+    /// characters encoded in UTF-8 if utf8 is set.
+    fn fmt_text(&self, value: &Value, utf8: bool) -> String {
+        let bytes = self.bytes(value);
+        let start = bytes
+            .iter()
+            .position(|&byte| byte != 0)
+            .unwrap_or(bytes.len() - 1);
+        let mut text = String::from("'");
+        if utf8 {
+            for chunk in bytes[start..].utf8_chunks() {
+                chunk
+                    .valid()
+                    .chars()
+                    .for_each(|c| escape_char(c, &mut text));
+                chunk
+                    .invalid()
+                    .iter()
+                    .for_each(|&byte| escape_byte(byte, &mut text));
+            }
         } else {
-            format!("{}{}", prefix, abs_num_str)
+            for &byte in &bytes[start..] {
+                if byte.is_ascii() {
+                    escape_char(byte as char, &mut text);
+                } else {
+                    escape_byte(byte, &mut text);
+                }
+            }
+        }
+        text.push('\'');
+        text
+    }
+}
+
+/// Pushes c onto text as it is written in a character literal.
+fn escape_char(c: char, text: &mut String) {
+    match c {
+        '\'' => text.push_str("\\'"),
+        '\\' => text.push_str("\\\\"),
+        '\n' => text.push_str("\\n"),
+        '\r' => text.push_str("\\r"),
+        '\t' => text.push_str("\\t"),
+        '\0' => text.push_str("\\0"),
+        c if c.is_control() => {
+            let mut buf = [0; 4];
+            for &byte in c.encode_utf8(&mut buf).as_bytes() {
+                escape_byte(byte, text);
+            }
         }
+        c => text.push(c),
     }
 }
 
+/// Pushes byte onto text as an escape of the form \xff.
+fn escape_byte(byte: u8, text: &mut String) {
+    text.push_str(&format!("\\x{:02x}", byte));
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -152,10 +1173,653 @@ mod test {
 
             // Regardless of punctuation, output should be the same
             of = of.with_punctuate_number(false);
-            assert_eq!(of.fmt(0), output);
+            assert_eq!(of.fmt(&Value::Fixed(0)), output);
 
             of = of.with_punctuate_number(true);
-            assert_eq!(of.fmt(0), output);
+            assert_eq!(of.fmt(&Value::Fixed(0)), output);
+        }
+    }
+
+    #[test]
+    fn test_word_fmt() {
+        let cases = [
+            (8, true, -1, "-0x1"),
+            (8, false, -1, "0xff"),
+            (16, false, 0x1_2345, "0x2345"),
+            (32, true, 0xffff_fff0, "-0x10"),
+            (64, false, -1, "0xffffffffffffffff"),
+            (128, false, -1, "0xffffffffffffffffffffffffffffffff"),
+            (128, true, i128::MIN, "-0x80000000000000000000000000000000"),
+            (64, true, i64::MIN as i128, "-0x8000000000000000"),
+        ];
+
+        for (bits, signed, num, output) in cases {
+            let word = Word::new(bits.try_into().unwrap(), signed);
+            let of = OutputFormat::default().with_word(word);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+    }
+
+    #[test]
+    fn test_twos_fmt() {
+        let cases = [
+            (8, true, FormatRadix::Hex, -1, "0xff"),
+            (32, true, FormatRadix::Hex, -0x10, "0xfffffff0"),
+            (16, true, FormatRadix::Octal, -8, "0o177770"),
+            (8, true, FormatRadix::Binary, -128, "0b10000000"),
+            (
+                64,
+                true,
+                FormatRadix::Hex,
+                i64::MIN as i128,
+                "0x8000000000000000",
+            ),
+            (64, true, FormatRadix::Hex, 0x10, "0x10"),
+            (32, true, FormatRadix::Decimal, -0x10, "-16"),
+            (8, false, FormatRadix::Hex, -1, "0xff"),
+        ];
+
+        for (bits, signed, radix, num, output) in cases {
+            let word = Word::new(bits.try_into().unwrap(), signed);
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_word(word)
+                .with_negative(Negative::Twos);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+    }
+
+    #[test]
+    fn test_pad_fmt() {
+        let cases = [
+            (
+                64,
+                false,
+                FormatRadix::Hex,
+                Pad::Word,
+                true,
+                0x7fff_0000_1000,
+                "0x0000_7fff_0000_1000",
+            ),
+            (
+                64,
+                false,
+                FormatRadix::Hex,
+                Pad::Word,
+                false,
+                0x7fff_0000_1000,
+                "0x00007fff00001000",
+            ),
+            (16, true, FormatRadix::Hex, Pad::Word, false, -1, "-0x0001"),
+            (
+                8,
+                false,
+                FormatRadix::Binary,
+                Pad::Word,
+                true,
+                5,
+                "0b0000_0101",
+            ),
+            (
+                16,
+                false,
+                FormatRadix::Octal,
+                Pad::Word,
+                false,
+                8,
+                "0o000010",
+            ),
+            (8, true, FormatRadix::Decimal, Pad::Word, false, 7, "007"),
+            (
+                32,
+                false,
+                FormatRadix::Decimal,
+                Pad::Word,
+                true,
+                1000,
+                "0_000_001_000",
+            ),
+            (
+                32,
+                false,
+                FormatRadix::Hex,
+                Pad::Digits(6),
+                true,
+                0xabc,
+                "0x00_0abc",
+            ),
+            (
+                32,
+                false,
+                FormatRadix::Hex,
+                Pad::Digits(2),
+                false,
+                0xabc,
+                "0xabc",
+            ),
+            (32, false, FormatRadix::Hex, Pad::Off, false, 0xabc, "0xabc"),
+        ];
+
+        for (bits, signed, radix, pad, punctuate, num, output) in cases {
+            let word = Word::new(bits.try_into().unwrap(), signed);
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_word(word)
+                .with_punctuate_number(punctuate)
+                .with_pad(pad);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+    }
+
+    #[test]
+    fn test_separator_fmt() {
+        let cases = [
+            (',', FormatRadix::Decimal, 3, 1_000_000, "1,000,000"),
+            ('\'', FormatRadix::Decimal, 4, 1_000_000, "100'0000"),
+            (' ', FormatRadix::Binary, 8, 0x1ff, "0b1 11111111"),
+            ('_', FormatRadix::Hex, 2, 0xcafe, "0xca_fe"),
+            ('_', FormatRadix::Hex, 0, 0xcafe, "0xcafe"),
+        ];
+
+        for (sep, radix, size, num, output) in cases {
+            let mut group_sizes = GroupSizes::default();
+            group_sizes.set(radix, size);
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_punctuate_number(true)
+                .with_separator(sep.try_into().unwrap())
+                .with_group_sizes(group_sizes);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+
+        let of = OutputFormat::default()
+            .with_format_radix(FormatRadix::Binary)
+            .with_punctuate_number(true)
+            .with_group_sizes(GroupSizes {
+                binary: 8,
+                ..Default::default()
+            })
+            .with_word(Word::new(16.try_into().unwrap(), false))
+            .with_pad(Pad::Word);
+        assert_eq!(of.fmt(&Value::Fixed(5)), "0b00000000_00000101");
+    }
+
+    #[test]
+    fn test_separator_parse() {
+        assert_eq!(",".parse(), Ok(Separator(',')));
+        assert_eq!("'".parse(), Ok(Separator('\'')));
+        assert_eq!("space".parse(), Ok(Separator(' ')));
+        assert!(".".parse::<Separator>().is_err());
+        assert!("__".parse::<Separator>().is_err());
+        assert_eq!(
+            "bin=8".parse(),
+            Ok(GroupSize {
+                radix: FormatRadix::Binary,
+                size: 8
+            })
+        );
+        assert!("bin8".parse::<GroupSize>().is_err());
+        assert!("byte=8".parse::<GroupSize>().is_err());
+    }
+
+    #[test]
+    fn test_prefix_fmt() {
+        let cases = [
+            (Prefix::C, Case::Upper, FormatRadix::Hex, 0xcafe, "0xCAFE"),
+            (Prefix::C, Case::Lower, FormatRadix::Octal, 0o17, "0o17"),
+            (
+                Prefix::Intel,
+                Case::Upper,
+                FormatRadix::Hex,
+                0xcafe,
+                "0CAFEh",
+            ),
+            (Prefix::Intel, Case::Upper, FormatRadix::Hex, 0x1f, "1Fh"),
+            (Prefix::Intel, Case::Lower, FormatRadix::Octal, 0o17, "17o"),
+            (Prefix::Intel, Case::Lower, FormatRadix::Binary, 5, "101b"),
+            (
+                Prefix::Motorola,
+                Case::Upper,
+                FormatRadix::Hex,
+                0xcafe,
+                "$CAFE",
+            ),
+            (
+                Prefix::Motorola,
+                Case::Lower,
+                FormatRadix::Octal,
+                0o17,
+                "@17",
+            ),
+            (
+                Prefix::Motorola,
+                Case::Lower,
+                FormatRadix::Binary,
+                5,
+                "%101",
+            ),
+            (
+                Prefix::Ada,
+                Case::Upper,
+                FormatRadix::Hex,
+                0xcafe,
+                "16#CAFE#",
+            ),
+            (Prefix::Ada, Case::Lower, FormatRadix::Octal, 0o17, "8#17#"),
+            (Prefix::Ada, Case::Lower, FormatRadix::Binary, 5, "2#101#"),
+            (Prefix::None, Case::Lower, FormatRadix::Hex, 0xcafe, "cafe"),
+            (Prefix::Ada, Case::Upper, FormatRadix::Decimal, 42, "42"),
+            (
+                Prefix::Motorola,
+                Case::Upper,
+                FormatRadix::Hex,
+                -0xcafe,
+                "-$CAFE",
+            ),
+        ];
+
+        for (prefix, case, radix, num, output) in cases {
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_prefix(prefix)
+                .with_case(case);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+
+        let of = OutputFormat::default()
+            .with_prefix(Prefix::Intel)
+            .with_punctuate_number(true);
+        assert_eq!(of.fmt(&Value::Fixed(0xcafe_babe)), "0cafe_babeh");
+    }
+
+    #[test]
+    fn test_base_fmt() {
+        let base = |radix: u32| FormatRadix::Base(radix.try_into().unwrap());
+        let cases = [
+            (base(36), Prefix::C, 1295, "36#zz"),
+            (base(36), Prefix::Ada, 1295, "36#zz#"),
+            (base(36), Prefix::None, 1295, "36#zz"),
+            (base(36), Prefix::None, 0xcafe, "36#143i"),
+            (base(3), Prefix::Intel, 48, "3#1210"),
+            (base(3), Prefix::C, -48, "-3#1210"),
+            (base(32), Prefix::Motorola, 0, "32#0"),
+        ];
+        for (radix, prefix, num, output) in cases {
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_prefix(prefix);
+            assert_eq!(of.fmt(&Value::Fixed(num)), output);
+        }
+
+        let of = OutputFormat::default()
+            .with_format_radix(base(36))
+            .with_punctuate_number(true)
+            .with_case(Case::Upper);
+        assert_eq!(of.fmt(&Value::Fixed(36i128.pow(6) - 1)), "36#ZZ_ZZZZ");
+    }
+
+    #[test]
+    fn test_radix_parse() {
+        assert_eq!("hex".parse(), Ok(FormatRadix::Hex));
+        assert_eq!("base(16)".parse(), Ok(FormatRadix::Hex));
+        assert_eq!(
+            "base(36)".parse(),
+            Ok(FormatRadix::Base(Base::try_from(36).unwrap()))
+        );
+        assert!("base(37)".parse::<FormatRadix>().is_err());
+        assert!("base(1)".parse::<FormatRadix>().is_err());
+        assert!("base36".parse::<FormatRadix>().is_err());
+    }
+
+    #[test]
+    fn test_ruler_fmt() {
+        let cases = [
+            (
+                Prefix::C,
+                false,
+                0x4142,
+                "0b100000101000010",
+                "   12   8   4   0",
+            ),
+            (
+                Prefix::C,
+                true,
+                0xcafe,
+                "0b1100_1010_1111_1110",
+                "    12    8    4    0",
+            ),
+            (Prefix::Intel, false, 0x3ff, "1111111111b", " 8   4   0"),
+            (Prefix::Ada, false, 1, "2#1#", "  0"),
+            (Prefix::C, false, -1, "-0b1", "   0"),
+        ];
+        for (prefix, punctuate, num, binary, ruler) in cases {
+            let of = OutputFormat::default()
+                .with_format_radix(FormatRadix::Binary)
+                .with_prefix(prefix)
+                .with_punctuate_number(punctuate);
+            assert_eq!(of.fmt(&Value::Fixed(num)), binary);
+            assert_eq!(of.fmt_ruler(&Value::Fixed(num)), ruler);
+        }
+
+        let of = OutputFormat::default().with_prefix(Prefix::None);
+        let ruler = of.fmt_ruler(&Value::Fixed(0x3fff_ffff_ffff));
+        assert_eq!(ruler, "44  40  36  32  28  24  20  16  12   8   4   0");
+    }
+
+    #[test]
+    fn test_text_fmt() {
+        let u16 = Word::new(16.try_into().unwrap(), false);
+        let cases = [
+            (Word::default(), 0x4142, false, r"'AB'"),
+            (Word::default(), 0x41_0042, false, r"'A\0B'"),
+            (Word::default(), 0, false, r"'\0'"),
+            (u16, -1, false, r"'\xff\xff'"),
+            (u16, 0x2021, false, r"' !'"),
+            (Word::default(), 0x7f45_4c46, false, r"'\x7fELF'"),
+            (Word::default(), 0x275c_0a09, false, r"'\'\\\n\t'"),
+            (Word::default(), 0xc3a9, false, r"'\xc3\xa9'"),
+            (Word::default(), 0xc3a9, true, r"'é'"),
+            (Word::default(), 0x0041_c3ff, true, r"'A\xc3\xff'"),
+            (Word::default(), 0xc285, true, r"'\xc2\x85'"),
+        ];
+        for (word, num, utf8, output) in cases {
+            let of = OutputFormat::default().with_word(word);
+            assert_eq!(of.fmt_text(&Value::Fixed(num), utf8), output);
+        }
+    }
+
+    #[test]
+    fn test_bytes_fmt() {
+        let u16 = Word::new(16.try_into().unwrap(), false);
+        let cases = [
+            (
+                Word::default(),
+                0xdead_beef,
+                Endian::Big,
+                Case::Lower,
+                "00 00 00 00 de ad be ef",
+            ),
+            (
+                Word::default(),
+                0xdead_beef,
+                Endian::Little,
+                Case::Upper,
+                "EF BE AD DE 00 00 00 00",
+            ),
+            (u16, -2, Endian::Big, Case::Lower, "ff fe"),
+            (u16, 0x1234, Endian::Little, Case::Lower, "34 12"),
+        ];
+        for (word, num, endian, case, output) in cases {
+            let of = OutputFormat::default().with_word(word).with_case(case);
+            assert_eq!(of.fmt_bytes(&Value::Fixed(num), endian), output);
+        }
+    }
+
+    #[test]
+    fn test_float_fmt() {
+        let of = OutputFormat::default();
+        assert_eq!(
+            of.fmt_float(&Value::Fixed(0x3fc0_0000), FloatFormat::F32),
+            "1.5\nsign[31]        = 0x0\nexponent[30:23] = 0x7f\nmantissa[22:0]  = 0x400000"
+        );
+        assert_eq!(
+            of.fmt_float(&Value::Fixed(-0x4000), FloatFormat::F16),
+            "-2.0\nsign[15]        = 0x1\nexponent[14:10] = 0x10\nmantissa[9:0]   = 0x0"
+        );
+
+        let of = OutputFormat::default()
+            .with_format_radix(FormatRadix::Decimal)
+            .with_word(Word::new(32.try_into().unwrap(), true));
+        assert_eq!(
+            of.fmt_float(&Value::Fixed(-1), FloatFormat::F64),
+            "2.1219957905e-314\nsign[63]        = 0\nexponent[62:52] = 0\nmantissa[51:0]  = 4294967295"
+        );
+    }
+
+    #[test]
+    fn test_fixed_fmt() {
+        let q = |s: &str| Target::Fixed(s.parse().unwrap());
+        let u16 = Word::new(16.try_into().unwrap(), false);
+        let i16 = Word::new(16.try_into().unwrap(), true);
+        let cases = [
+            (Word::default(), 0x18000, "q16.16", "1.5"),
+            (Word::default(), -0x4000, "q15", "-0.5"),
+            (u16, 0xc000, "q15", "-0.5"),
+            (u16, 0xc000, "q16.16", "0.75"),
+            (i16, -0x8000, "q16.16", "-0.5"),
+            (i16, 0x4000, "q16.16", "0.25"),
+        ];
+        for (word, num, format, output) in cases {
+            let of = OutputFormat::default().with_word(word);
+            assert_eq!(of.fmt_to(q(format), &Value::Fixed(num)), output);
+        }
+        #[cfg(feature = "bignum")]
+        assert_eq!(
+            OutputFormat::default().fmt_to(q("q16.16"), &Value::Big((-0x8000).into())),
+            "-0.5"
+        );
+    }
+
+    #[test]
+    fn test_size_fmt() {
+        let cases = [
+            (SizePrefixes::Binary, 0x4000_0000, "1 GiB"),
+            (SizePrefixes::Binary, 0x6000_0000, "1.5 GiB"),
+            (SizePrefixes::Binary, 0x4000_1000, "~1 GiB"),
+            (SizePrefixes::Binary, 1_500_000_000, "~1.4 GiB"),
+            (SizePrefixes::Binary, 4096, "4 KiB"),
+            (SizePrefixes::Binary, 1023, "1023 B"),
+            (SizePrefixes::Binary, 0, "0 B"),
+            (SizePrefixes::Binary, -2048, "-2 KiB"),
+            (SizePrefixes::Binary, -1537, "-~1.5 KiB"),
+            (SizePrefixes::Binary, 1 << 62, "4 EiB"),
+            (SizePrefixes::Si, 0x4000_0000, "~1.07 GB"),
+            (SizePrefixes::Si, 1500, "1.5 kB"),
+            (SizePrefixes::Si, 999_999, "~1000 kB"),
+        ];
+        for (prefixes, num, output) in cases {
+            let of = OutputFormat::default().with_size_prefixes(prefixes);
+            assert_eq!(of.fmt_size(&Value::Fixed(num)), output, "{}", num);
+        }
+
+        let u128 = Word::new(128.try_into().unwrap(), false);
+        let of = OutputFormat::default().with_word(u128);
+        assert_eq!(of.fmt_size(&Value::Fixed(-1)), "~295147905179352825856 EiB");
+        assert_eq!(
+            of.fmt_size(&Value::Fixed(1 << 127)),
+            "147573952589676412928 EiB"
+        );
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let of = OutputFormat::default().with_word(u8);
+        assert_eq!(of.fmt_size(&Value::Fixed(-1)), "255 B");
+    }
+
+    #[test]
+    fn test_unit_fmt() {
+        let of = OutputFormat::default();
+        let value = Value::Fixed(10_500_000);
+        assert_eq!(of.fmt_to(Target::Unit(Unit::Ms), &value), "10.5 ms");
+        assert_eq!(of.fmt_to(Target::Unit(Unit::Ns), &value), "10500000 ns");
+        let of = of.with_punctuate_number(true);
+        assert_eq!(of.fmt_to(Target::Ticks, &value), "10_500_000 ticks");
+
+        let of = OutputFormat::default().with_word(Word::new(128.try_into().unwrap(), false));
+        assert_eq!(
+            of.fmt_to(Target::Unit(Unit::Ms), &Value::Fixed(-1)),
+            "340282366920938463463374607431768.211455 ms"
+        );
+        assert_eq!(
+            of.fmt_to(Target::Unit(Unit::MHz), &Value::Fixed(1 << 127)),
+            "170141183460469231731687303715884.105728 MHz"
+        );
+    }
+
+    #[test]
+    fn test_all_fmt() {
+        let of = OutputFormat::default().with_rows(vec![
+            Row::Ascii,
+            Row::Ruler,
+            Row::Signed,
+            Row::Unsigned,
+        ]);
+        let table = of.fmt_all(&Value::Fixed(-2));
+        let rows: Vec<_> = table
+            .lines()
+            .map(|line| line.split_once(": ").map(|(_, num)| num))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                Some("-2"),
+                Some("-0x2"),
+                Some("-0o2"),
+                Some("-0b10"),
+                None,
+                Some(r"'\xff\xff\xff\xff\xff\xff\xff\xfe'"),
+                Some("-2"),
+                Some("18446744073709551614"),
+            ]
+        );
+
+        let of =
+            OutputFormat::default().with_format_radix(FormatRadix::Base(36.try_into().unwrap()));
+        let table = of.fmt_all(&Value::Fixed(35));
+        assert_eq!(table.lines().count(), 5);
+        assert!(table.lines().nth(3).unwrap().ends_with(": 36#z"));
+    }
+
+    #[test]
+    fn test_bits_fmt() {
+        colored::control::set_override(false);
+        let u16 = Word::new(16.try_into().unwrap(), false);
+        let of = OutputFormat::default().with_word(u16);
+        assert_eq!(
+            of.fmt_bits(&Value::Fixed(0xcafe), BitOrder::Msb),
+            "15   11    7    3\n1100 1010  1111 1110"
+        );
+        assert_eq!(
+            of.fmt_bits(&Value::Fixed(0xcafe), BitOrder::Lsb),
+            "0    4     8    12\n0111 1111  0101 0011"
+        );
+
+        let i64 = Word::new(64.try_into().unwrap(), true);
+        let of = OutputFormat::default().with_word(i64);
+        let lines = [
+            "63   59    55   51    47   43    39   35",
+            "1111 1111  1111 1111  1111 1111  1111 1111",
+            "31   27    23   19    15   11    7    3",
+            "1111 1111  1111 1111  1111 1111  1111 1110",
+        ];
+        assert_eq!(
+            of.fmt_bits(&Value::Fixed(-2), BitOrder::Msb),
+            lines.join("\n")
+        );
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_bits_fmt() {
+        colored::control::set_override(false);
+        let of = OutputFormat::default();
+        let big = Value::Big(num_bigint::BigInt::from(1u8) << 10000);
+        let bits = of.fmt_bits(&big, BitOrder::Msb);
+        let lines: Vec<_> = bits.lines().collect();
+        // the indices are 5 digits wide, so every nibble is too
+        assert_eq!(
+            lines[0],
+            "10007 10003  9999  9995   9991  9987   9983  9979"
+        );
+        assert_eq!(
+            lines[1],
+            "0000  0001   0000  0000   0000  0000   0000  0000"
+        );
+    }
+
+    #[test]
+    fn test_target_parse() {
+        assert_eq!("all".parse(), Ok(Target::All));
+        assert_eq!("bits".parse(), Ok(Target::Bits(BitOrder::Msb)));
+        assert_eq!("str".parse(), Ok(Target::Str));
+        assert_eq!("bits lsb".parse(), Ok(Target::Bits(BitOrder::Lsb)));
+        assert!("bits both".parse::<Target>().is_err());
+        assert_eq!("bytes".parse(), Ok(Target::Bytes(Endian::Big)));
+        assert_eq!("bytes le".parse(), Ok(Target::Bytes(Endian::Little)));
+        assert!("bytes lsb".parse::<Target>().is_err());
+        assert_eq!("bf16".parse(), Ok(Target::Float(FloatFormat::Bf16)));
+        assert_eq!("q15".parse(), Ok(Target::Fixed("q1.15".parse().unwrap())));
+        assert!("q0.15".parse::<Target>().is_err());
+        assert_eq!("size".parse(), Ok(Target::Size));
+        assert_eq!("ticks".parse(), Ok(Target::Ticks));
+        assert_eq!("kHz".parse(), Ok(Target::Unit(Unit::KHz)));
+        assert_eq!("si".parse(), Ok(SizePrefixes::Si));
+        assert_eq!("oct".parse(), Ok(Target::Radix(FormatRadix::Octal)));
+        assert!("every".parse::<Target>().is_err());
+        assert_eq!("ruler".parse(), Ok(Row::Ruler));
+        assert!("float".parse::<Row>().is_err());
+    }
+
+    #[test]
+    fn test_prefix_parse() {
+        assert_eq!("intel".parse(), Ok(Prefix::Intel));
+        assert_eq!("none".parse(), Ok(Prefix::None));
+        assert!("pascal".parse::<Prefix>().is_err());
+        assert_eq!("upper".parse(), Ok(Case::Upper));
+        assert!("title".parse::<Case>().is_err());
+    }
+
+    #[test]
+    fn test_pad_parse() {
+        assert_eq!("on".parse(), Ok(Pad::Word));
+        assert_eq!("off".parse(), Ok(Pad::Off));
+        assert_eq!("16".parse(), Ok(Pad::Digits(16)));
+        assert_eq!("0".parse(), Ok(Pad::Off));
+        assert!("-1".parse::<Pad>().is_err());
+        assert!("wide".parse::<Pad>().is_err());
+    }
+
+    #[test]
+    fn test_negative_parse() {
+        assert_eq!("sign".parse(), Ok(Negative::Sign));
+        assert_eq!("twos".parse(), Ok(Negative::Twos));
+        assert!("ones".parse::<Negative>().is_err());
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_fmt() {
+        let cases = [
+            (
+                FormatRadix::Hex,
+                false,
+                "0x1_0000_0000_0000_0000_0000_0000_0000_0000",
+            ),
+            (
+                FormatRadix::Hex,
+                true,
+                "-0x100000000000000000000000000000000",
+            ),
+            (
+                FormatRadix::Decimal,
+                false,
+                "340_282_366_920_938_463_463_374_607_431_768_211_456",
+            ),
+            (
+                FormatRadix::Octal,
+                false,
+                "0o4_000_000_000_000_000_000_000_000_000_000_000_000_000_000",
+            ),
+        ];
+
+        // the word is ignored for big values
+        let word = Word::new(8.try_into().unwrap(), false);
+        for (radix, negative, output) in cases {
+            let num = num_bigint::BigInt::from(u128::MAX) + 1u8;
+            let num = if negative { -num } else { num };
+            let of = OutputFormat::default()
+                .with_format_radix(radix)
+                .with_punctuate_number(!negative)
+                .with_word(word);
+            assert_eq!(of.fmt(&Value::Big(num)), output);
         }
     }
 }
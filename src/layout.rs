@@ -0,0 +1,266 @@
+use std::{fmt, str::FromStr};
+
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
+use serde::Deserialize;
+
+use crate::error::CorkError;
+use crate::format::OutputFormat;
+use crate::value::Value;
+#[cfg(feature = "bignum")]
+use crate::value::MAX_BIG_BITS;
+use crate::word::Word;
+
+/// The number of bits that fields may range over, so that decoding stays bounded.
+#[cfg(not(feature = "bignum"))]
+const MAX_FIELD_BITS: u32 = 128;
+#[cfg(feature = "bignum")]
+const MAX_FIELD_BITS: u32 = MAX_BIG_BITS as u32;
+
+/// A Field is a named range of bits of a register, from hi down to lo, both inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    name: String,
+    hi: u32,
+    lo: u32,
+}
+
+impl Field {
+    /// Returns the bits of value that belong to the field, as an unsigned number.
+    fn extract(&self, value: &Value, word: Word) -> Result<Value, CorkError> {
+        let count = self.hi - self.lo + 1;
+        match value {
+            Value::Fixed(num) => {
+                if self.hi >= word.bits() {
+                    return Err(CorkError::BitOutOfRange {
+                        index: self.hi.to_string(),
+                        word,
+                    });
+                }
+                let mask = u128::MAX >> (128 - count);
+                Ok(Value::Fixed(
+                    ((word.bits_of(*num) >> self.lo) & mask) as i128,
+                ))
+            }
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => {
+                let mask = (BigInt::from(1u8) << count) - 1u8;
+                Ok(Value::Big((num >> self.lo) & mask))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.hi == self.lo {
+            write!(f, "{}[{}]", self.name, self.hi)
+        } else {
+            write!(f, "{}[{}:{}]", self.name, self.hi, self.lo)
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    /// Parses a field of the form "name=bit" or "name=lo:hi", where the bounds
+    /// may also be given as hi:lo.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, bits)) = s.split_once('=') else {
+            return Err(format!("{} is not of the form name=bits", s.trim()));
+        };
+        let name = name.trim();
+        let is_ident = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_ident {
+            return Err(format!("{} is not a valid field name", name));
+        }
+        let parse_bit = |bit: &str| {
+            bit.trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&bit| bit < MAX_FIELD_BITS)
+                .ok_or_else(|| format!("{} is not a valid bit of {}", bit.trim(), name))
+        };
+        let (hi, lo) = match bits.split_once(':') {
+            Some((a, b)) => {
+                let (a, b) = (parse_bit(a)?, parse_bit(b)?);
+                (a.max(b), a.min(b))
+            }
+            None => {
+                let bit = parse_bit(bits)?;
+                (bit, bit)
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            hi,
+            lo,
+        })
+    }
+}
+
+/// A Layout is the list of fields of a register, such as "PE=0, MP=1, PG=31".
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Layout {
+    fields: Vec<Field>,
+}
+
+impl Layout {
+    /// Returns every field along with its value in value, in the order of the layout.
+    pub fn decode(&self, value: &Value, word: Word) -> Result<Vec<(&Field, Value)>, CorkError> {
+        self.fields
+            .iter()
+            .map(|field| Ok((field, field.extract(value, word)?)))
+            .collect()
+    }
+
+    /// fmt_decoded formats the fields of value one per line, as "name[hi:lo] = field".
+    /// Fields are unsigned, whatever the signedness of the word is.
+    pub fn fmt_decoded(
+        &self,
+        value: &Value,
+        word: Word,
+        of: &OutputFormat,
+    ) -> Result<String, CorkError> {
+        let mut unsigned = word;
+        unsigned.set_signed(false);
+        let of = of.clone().with_word(unsigned);
+        let fields = self.decode(value, word)?;
+        let labels: Vec<_> = fields.iter().map(|(field, _)| field.to_string()).collect();
+        let width = labels.iter().map(String::len).max().unwrap_or(0);
+        let lines: Vec<_> = labels
+            .iter()
+            .zip(&fields)
+            .map(|(label, (_, field))| format!("{:<width$} = {}", label, of.fmt(field)))
+            .collect();
+        Ok(lines.join("\n"))
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(str::parse)
+            .collect::<Result<Vec<Field>, _>>()?;
+        for (i, field) in fields.iter().enumerate() {
+            if fields[..i].iter().any(|other| other.name == field.name) {
+                return Err(format!("there is more than one field named {}", field.name));
+            }
+        }
+        Ok(Self { fields })
+    }
+}
+
+impl TryFrom<String> for Layout {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(bits: u32, signed: bool) -> Word {
+        Word::new(bits.try_into().unwrap(), signed)
+    }
+
+    #[test]
+    fn test_layout_parse() {
+        let layout: Layout = "present=0, rw = 1,pfn=12:51, avl=11:9".parse().unwrap();
+        let fields: Vec<_> = layout.fields.iter().map(Field::to_string).collect();
+        assert_eq!(fields, ["present[0]", "rw[1]", "pfn[51:12]", "avl[11:9]"]);
+
+        let errors = [
+            "",
+            "PE",
+            "PE=",
+            "PE=x",
+            "1PE=0",
+            "P E=0",
+            "PE=0:",
+            "PE=0, PE=1",
+            "PE=65536",
+        ];
+        for layout in errors {
+            assert!(layout.parse::<Layout>().is_err(), "{}", layout);
+        }
+    }
+
+    #[test]
+    fn test_layout_decode() {
+        let layout: Layout = "present=0, rw=1, pfn=12:51, nx=63".parse().unwrap();
+        let pte = 0x8000_0000_1234_5003_u64 as i64 as i128;
+        let fields: Vec<_> = layout
+            .decode(&Value::Fixed(pte), word(64, true))
+            .unwrap()
+            .into_iter()
+            .map(|(field, value)| (field.name.as_str(), value))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("present", Value::Fixed(1)),
+                ("rw", Value::Fixed(1)),
+                ("pfn", Value::Fixed(0x12345)),
+                ("nx", Value::Fixed(1)),
+            ]
+        );
+
+        assert_eq!(
+            layout.decode(&Value::Fixed(pte), word(32, false)),
+            Err(CorkError::BitOutOfRange {
+                index: String::from("51"),
+                word: word(32, false),
+            })
+        );
+    }
+
+    #[test]
+    fn test_layout_fmt() {
+        let layout: Layout = "PE=0, MP=1, PG=31".parse().unwrap();
+        let of = OutputFormat::default();
+        assert_eq!(
+            layout
+                .fmt_decoded(&Value::Fixed(-0x7fff_fffe), word(32, true), &of)
+                .unwrap(),
+            "PE[0]  = 0x0\nMP[1]  = 0x1\nPG[31] = 0x1"
+        );
+
+        let layout: Layout = "all=0:31".parse().unwrap();
+        assert_eq!(
+            layout
+                .fmt_decoded(&Value::Fixed(-1), word(32, true), &of)
+                .unwrap(),
+            "all[31:0] = 0xffffffff"
+        );
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_layout_decode() {
+        let layout: Layout = "low=0:7, high=200:207".parse().unwrap();
+        let num = (BigInt::from(0xa5u8) << 200) + 0x3cu8;
+        let values: Vec<_> = layout
+            .decode(&Value::Big(num), Word::default())
+            .unwrap()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(
+            values,
+            [
+                Value::Big(BigInt::from(0x3c)),
+                Value::Big(BigInt::from(0xa5))
+            ]
+        );
+    }
+}
@@ -7,18 +7,24 @@ use std::fs::File;
 use std::io::{self, BufRead};
 use std::path::PathBuf;
 use std::process::exit;
-use strum::IntoEnumIterator;
 
 use crate::{
-    format::{FormatRadix, OutputFormat},
+    expression::{eval::Env, SetDirective},
+    format::{OutputFormat, Target},
     options::Options,
 };
 
 mod config;
 mod error;
 mod expression;
+mod fixed;
+mod float;
 mod format;
+mod layout;
 mod options;
+mod unit;
+mod value;
+mod word;
 
 fn main() {
     let options = Options::parse();
@@ -55,10 +61,8 @@ fn script_evaluate(file_path: &str, config: &Config) {
 
     let lines = io::BufReader::new(file).lines();
 
-    let mut ans = 0;
-    let mut of = OutputFormat::default()
-        .with_format_radix(*config.output_radix())
-        .with_punctuate_number(*config.punctuate_output());
+    let mut env = config.env();
+    let mut of = config.output_format();
 
     for line in lines {
         let line = match line {
@@ -72,10 +76,10 @@ fn script_evaluate(file_path: &str, config: &Config) {
             warranty();
             continue;
         }
-        match proccess_command(line, &mut ans, &mut of) {
+        match proccess_command(&line, &mut env, &mut of) {
             Ok(_) => continue,
             Err(e) => {
-                eprintln!("{}", e);
+                report_error(&line, &e);
                 exit(1);
             }
         };
@@ -83,29 +87,15 @@ fn script_evaluate(file_path: &str, config: &Config) {
 }
 
 fn inline_evaluate(expr_str: &str, config: &Config, options: &Options) {
+    let env = config.env();
     match expression::parse_line(expr_str) {
         Ok(command) => match command {
-            expression::Command::Expr(expr) => match expression::eval::eval_expr(&expr, 0) {
+            expression::Command::Expr(expr) => match expression::eval::eval_expr(&expr, &env) {
                 Ok(ans) => {
                     if options.all {
-                        for radix in FormatRadix::iter() {
-                            println!(
-                                "{:>21}: {}",
-                                radix.to_string(),
-                                OutputFormat::default()
-                                    .with_format_radix(radix)
-                                    .with_punctuate_number(*config.punctuate_output())
-                                    .fmt(ans),
-                            );
-                        }
+                        println!("{}", config.output_format().fmt_all(&ans));
                     } else {
-                        println!(
-                            "{}",
-                            OutputFormat::default()
-                                .with_format_radix(*config.output_radix())
-                                .with_punctuate_number(*config.punctuate_output())
-                                .fmt(ans),
-                        );
+                        println!("{}", config.output_format().fmt(&ans),);
                     }
                 }
                 Err(err) => {
@@ -117,14 +107,41 @@ fn inline_evaluate(expr_str: &str, config: &Config, options: &Options) {
                 eprintln!("Set directive not allowed in inline-expression");
                 exit(1);
             }
-            expression::Command::Convert(conversion) => match conversion.value(0) {
+            expression::Command::Assign(_) => {
+                eprintln!("Assignment not allowed in inline-expression");
+                exit(1);
+            }
+            expression::Command::Define(_) => {
+                eprintln!("Function definition not allowed in inline-expression");
+                exit(1);
+            }
+            expression::Command::DefineLayout(_) => {
+                eprintln!("Layout definition not allowed in inline-expression");
+                exit(1);
+            }
+            expression::Command::Decode(decode) => {
+                let fields = decode.value(&env).and_then(|ans| {
+                    decode
+                        .layout(&env)?
+                        .fmt_decoded(&ans, env.word(), &config.output_format())
+                });
+                match fields {
+                    Ok(fields) => println!("{}", fields),
+                    Err(err) => {
+                        eprintln!("Failed to evaluate \"{}\": {}", expr_str, err);
+                        exit(1);
+                    }
+                }
+            }
+            expression::Command::Vars => {
+                eprintln!("Vars command not allowed in inline-expression");
+                exit(1);
+            }
+            expression::Command::Convert(conversion) => match conversion.value(&env) {
                 Ok(ans) => {
                     println!(
                         "{}",
-                        OutputFormat::default()
-                            .with_format_radix(conversion.radix())
-                            .with_punctuate_number(*config.punctuate_output())
-                            .fmt(ans)
+                        config.output_format().fmt_to(conversion.target(), &ans)
                     );
                 }
                 Err(err) => {
@@ -161,10 +178,8 @@ fn interactive(config: &Config) {
         println!("No existing history!\n");
     }
 
-    let mut of = OutputFormat::default()
-        .with_format_radix(*config.output_radix())
-        .with_punctuate_number(*config.punctuate_output());
-    let mut ans = 0;
+    let mut env = config.env();
+    let mut of = config.output_format();
     loop {
         match rl.readline(config.prompt()) {
             Ok(line) => {
@@ -173,11 +188,9 @@ fn interactive(config: &Config) {
                     warranty();
                     continue;
                 }
-                match proccess_command(line, &mut ans, &mut of) {
+                match proccess_command(&line, &mut env, &mut of) {
                     Ok(_) => continue,
-                    Err(e) => {
-                        eprintln!("{}", e);
-                    }
+                    Err(e) => report_error(&line, &e),
                 };
             }
             Err(ReadlineError::Eof) => {
@@ -197,48 +210,146 @@ fn interactive(config: &Config) {
     rl.save_history(&history_path).unwrap();
 }
 
-fn proccess_command(line: String, ans: &mut i64, of: &mut OutputFormat) -> Result<(), CorkError> {
-    let command = expression::parse_line(&line)?;
+/// Prints err, pointing at the part of line that it is about.
+fn report_error(line: &str, err: &CorkError) {
+    eprintln!("{}", err);
+    if let Some(span) = err.span() {
+        let start = line[..span.start].chars().count();
+        let len = line[span].chars().count();
+        eprintln!("{}", line);
+        eprintln!("{}{}", " ".repeat(start), "^".repeat(len));
+    }
+}
+
+fn proccess_command(line: &str, env: &mut Env, of: &mut OutputFormat) -> Result<(), CorkError> {
+    let command = expression::parse_line(line)?;
     match command {
         expression::Command::Expr(expr) => {
-            let val = expression::eval::eval_expr(&expr, *ans)?;
-            *ans = val;
-            println!("{}", of.fmt(val));
-        }
-        expression::Command::Set(set) => {
-            if set[0] == "of" {
-                match set[1].as_str() {
-                    "hex" => of.set_format_radix(FormatRadix::Hex),
-                    "dec" => of.set_format_radix(FormatRadix::Decimal),
-                    "oct" => of.set_format_radix(FormatRadix::Octal),
-                    "bin" => of.set_format_radix(FormatRadix::Binary),
-                    _ => {
-                        return Err(error::CorkError::InvalidValueForKey {
-                            key: set[0].clone(),
-                            value: set[1].clone(),
-                        });
-                    }
-                }
-            } else {
-                return Err(error::CorkError::InvalidKey(set[0].clone()));
-            }
+            let val = expression::eval::eval_expr(&expr, env)?;
+            println!("{}", of.show(&val));
+            env.set_ans(val, expression::eval::unit_dim(&expr, env)?);
         }
+        expression::Command::Set(set) => apply_set(&set, env, of)?,
         expression::Command::Convert(conversion) => {
-            let val = conversion.value(*ans)?;
-            *ans = val;
-            println!(
-                "{}",
-                OutputFormat::default()
-                    .with_format_radix(conversion.radix())
-                    .with_punctuate_number(of.punctuate_number())
-                    .fmt(val)
-            );
+            let (val, dim) = (conversion.value(env)?, conversion.dim(env)?);
+            println!("{}", of.fmt_to(conversion.target(), &val));
+            env.set_ans(val, dim);
+        }
+        expression::Command::Assign(assignment) => {
+            let (val, dim) = (assignment.value(env)?, assignment.dim(env)?);
+            env.set_var(assignment.name(), val, dim);
+        }
+        expression::Command::Decode(decode) => {
+            let (val, dim) = (decode.value(env)?, decode.dim(env)?);
+            println!("{}", decode.layout(env)?.fmt_decoded(&val, env.word(), of)?);
+            env.set_ans(val, dim);
+        }
+        expression::Command::Define(function) => env.define(function)?,
+        expression::Command::DefineLayout(definition) => {
+            env.define_layout(definition.name(), definition.layout().clone())
+        }
+        expression::Command::Vars => {
+            for (name, val) in env.vars() {
+                println!("{} = {}", name, of.fmt(val));
+            }
         }
         expression::Command::Empty => println!(),
     };
     Ok(())
 }
 
+fn apply_set(set: &SetDirective, env: &mut Env, of: &mut OutputFormat) -> Result<(), CorkError> {
+    match set.key() {
+        "of" => match set.values().join(" ").parse() {
+            Ok(Target::Radix(radix)) => {
+                of.set_format_radix(radix);
+                of.set_target(None);
+            }
+            Ok(target) => of.set_target(Some(target)),
+            Err(_) => return Err(set.invalid_value()),
+        },
+        "punctuate" => {
+            let punctuate = parse_switch(set.value()?).ok_or_else(|| set.invalid_value())?;
+            of.set_punctuate_number(punctuate);
+        }
+        "sep" => match set.values() {
+            [sep] => of.set_separator(sep.parse().map_err(|_| set.invalid_value())?),
+            [radix, size] => {
+                let radix = radix.parse().map_err(|_| set.invalid_value())?;
+                let size = size.parse().map_err(|_| set.invalid_value())?;
+                of.set_group_size(radix, size);
+            }
+            _ => return Err(set.invalid_value()),
+        },
+        #[cfg(feature = "bignum")]
+        "width" if set.value()? == "big" => {
+            env.set_big(true);
+        }
+        "width" => {
+            let width = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            let mut word = env.word();
+            word.set_width(width);
+            env.set_word(word);
+            of.set_word(word);
+            #[cfg(feature = "bignum")]
+            env.set_big(false);
+        }
+        "signed" => {
+            let signed = parse_switch(set.value()?).ok_or_else(|| set.invalid_value())?;
+            let mut word = env.word();
+            word.set_signed(signed);
+            env.set_word(word);
+            of.set_word(word);
+        }
+        "overflow" => {
+            let overflow = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            env.set_overflow(overflow);
+        }
+        "negative" => {
+            let negative = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            of.set_negative(negative);
+        }
+        "pad" => {
+            let pad = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            of.set_pad(pad);
+        }
+        "case" => {
+            let case = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            of.set_case(case);
+        }
+        "prefix" => {
+            let prefix = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            of.set_prefix(prefix);
+        }
+        "size" => {
+            let size_prefixes = set.value()?.parse().map_err(|_| set.invalid_value())?;
+            env.set_size_prefixes(size_prefixes);
+            of.set_size_prefixes(size_prefixes);
+        }
+        "rows" => match set.values() {
+            [none] if none == "none" => of.set_rows(Vec::new()),
+            rows => {
+                let rows = rows
+                    .iter()
+                    .map(|row| row.parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| set.invalid_value())?;
+                of.set_rows(rows);
+            }
+        },
+        key => return Err(CorkError::InvalidKey(key.to_string())),
+    }
+    Ok(())
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "on" | "true" => Some(true),
+        "off" | "false" => Some(false),
+        _ => None,
+    }
+}
+
 const LICENSE_HEADER: &str = "Copyright (C) 2021 Deep Majumder
 This is free software; see the source code for copying conditions.
 There is ABSOLUTELY NO WARRANTY; not even for MERCHANTABILITY or
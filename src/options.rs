@@ -1,5 +1,8 @@
 use clap::{ArgGroup, Parser};
 
+use crate::format::{GroupSize, Pad, Separator};
+use crate::word::{Overflow, Width};
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about)]
 #[clap(group(ArgGroup::new("base").args(&["all", "hex", "oct", "dec", "bin"])))]
@@ -16,6 +19,29 @@ pub struct Options {
     #[clap(short, long, help = "punctuate the output number")]
     pub punctuate_output: bool,
 
+    #[clap(
+        long,
+        value_name = "DIGITS",
+        num_args = 0..=1,
+        default_missing_value = "on",
+        help = "pad the output number with zeros to <DIGITS> digits, or to the word if omitted"
+    )]
+    pub pad: Option<Pad>,
+
+    #[clap(
+        long,
+        value_name = "SEP",
+        help = "separate groups of digits with <SEP> (_, comma, ' or space)"
+    )]
+    pub sep: Option<Separator>,
+
+    #[clap(
+        long,
+        value_name = "RADIX=SIZE",
+        help = "group the digits of <RADIX> (dec, hex, oct or bin) by <SIZE>"
+    )]
+    pub group: Vec<GroupSize>,
+
     #[clap(
         short,
         long,
@@ -32,6 +58,32 @@ pub struct Options {
     )]
     pub file: Option<String>,
 
+    #[clap(
+        short,
+        long,
+        value_name = "BITS",
+        help = "evaluate in words of <BITS> bits (8, 16, 32, 64 or 128)"
+    )]
+    pub width: Option<Width>,
+
+    #[clap(short, long, help = "evaluate in unsigned words")]
+    pub unsigned: bool,
+
+    #[clap(
+        short,
+        long,
+        conflicts_with = "unsigned",
+        help = "evaluate in signed words, even if the config file sets them unsigned"
+    )]
+    pub signed: bool,
+
+    #[clap(
+        long,
+        value_name = "POLICY",
+        help = "handle overflows by <POLICY> (checked, wrapping or saturating)"
+    )]
+    pub overflow: Option<Overflow>,
+
     #[clap(short, long, help = "print in all bases (only in expr eval mode)")]
     pub all: bool,
 
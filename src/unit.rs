@@ -0,0 +1,161 @@
+use std::{fmt, str::FromStr};
+
+/// A Dim is the dimension of a quantity: a plain number such as a tick count, a time, or a
+/// frequency. Times are held in nanoseconds and frequencies in hertz.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dim {
+    #[default]
+    Count,
+    Time,
+    Freq,
+    /// The dimension of a parameter of a function, which may be any of the others.
+    Unknown,
+}
+
+impl Dim {
+    /// Returns the dimension that a quantity of both self and other would have,
+    /// or None if they disagree.
+    pub fn common(self, other: Dim) -> Option<Dim> {
+        match (self, other) {
+            (Dim::Unknown, dim) | (dim, Dim::Unknown) => Some(dim),
+            _ if self == other => Some(self),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dim = match self {
+            Dim::Count => "a number",
+            Dim::Time => "a time",
+            Dim::Freq => "a frequency",
+            Dim::Unknown => "a value of unknown unit",
+        };
+        write!(f, "{}", dim)
+    }
+}
+
+/// A Unit is a unit of time or frequency that quantities can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Ns,
+    Us,
+    Ms,
+    S,
+    Hz,
+    KHz,
+    MHz,
+}
+
+impl Unit {
+    /// Returns the dimension of the quantities of the unit.
+    pub fn dim(self) -> Dim {
+        match self {
+            Unit::Ns | Unit::Us | Unit::Ms | Unit::S => Dim::Time,
+            Unit::Hz | Unit::KHz | Unit::MHz => Dim::Freq,
+        }
+    }
+
+    /// Returns how many nanoseconds or hertz the unit is.
+    pub fn scale(self) -> u128 {
+        match self {
+            Unit::Ns | Unit::Hz => 1,
+            Unit::Us | Unit::KHz => 1_000,
+            Unit::Ms | Unit::MHz => 1_000_000,
+            Unit::S => 1_000_000_000,
+        }
+    }
+
+    /// fmt_quantity formats a number of nanoseconds or hertz, given as its magnitude and
+    /// whether it is negative, in the unit. The quotient is shown in full, as it always has
+    /// a finite decimal expansion.
+    pub fn fmt_quantity(self, magnitude: u128, negative: bool) -> String {
+        let scale = self.scale();
+        let sign = if negative { "-" } else { "" };
+        let mut quantity = format!("{}{}", sign, magnitude / scale);
+        let frac = magnitude % scale;
+        if frac != 0 {
+            let digits = scale.ilog10() as usize;
+            quantity.push_str(format!(".{:0digits$}", frac).trim_end_matches('0'));
+        }
+        format!("{} {}", quantity, self)
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self {
+            Unit::Ns => "ns",
+            Unit::Us => "us",
+            Unit::Ms => "ms",
+            Unit::S => "s",
+            Unit::Hz => "Hz",
+            Unit::KHz => "kHz",
+            Unit::MHz => "MHz",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ns" => Ok(Unit::Ns),
+            "us" => Ok(Unit::Us),
+            "ms" => Ok(Unit::Ms),
+            "s" => Ok(Unit::S),
+            "Hz" => Ok(Unit::Hz),
+            "kHz" => Ok(Unit::KHz),
+            "MHz" => Ok(Unit::MHz),
+            _ => Err(format!(
+                "{} is not a valid unit (expected ns, us, ms, s, Hz, kHz or MHz)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_unit_parse() {
+        for unit in ["ns", "us", "ms", "s", "Hz", "kHz", "MHz"] {
+            assert_eq!(unit.parse::<Unit>().unwrap().to_string(), unit);
+        }
+        assert_eq!("MHz".parse::<Unit>().unwrap().dim(), Dim::Freq);
+        assert!("hz".parse::<Unit>().is_err());
+        assert!("min".parse::<Unit>().is_err());
+    }
+
+    #[test]
+    fn test_dim_common() {
+        assert_eq!(Dim::Time.common(Dim::Time), Some(Dim::Time));
+        assert_eq!(Dim::Time.common(Dim::Freq), None);
+        assert_eq!(Dim::Count.common(Dim::Time), None);
+        assert_eq!(Dim::Unknown.common(Dim::Freq), Some(Dim::Freq));
+        assert_eq!(Dim::Count.common(Dim::Unknown), Some(Dim::Count));
+    }
+
+    #[test]
+    fn test_quantity_fmt() {
+        let cases = [
+            (Unit::Ms, 10_000_000i128, "10 ms"),
+            (Unit::Ms, 10_500_000, "10.5 ms"),
+            (Unit::Us, 20_833, "20.833 us"),
+            (Unit::S, 1, "0.000000001 s"),
+            (Unit::Ns, -5, "-5 ns"),
+            (Unit::Ms, -1, "-0.000001 ms"),
+            (Unit::MHz, 48_000_000, "48 MHz"),
+            (Unit::KHz, 32_768, "32.768 kHz"),
+            (Unit::Hz, 0, "0 Hz"),
+        ];
+        for (unit, num, output) in cases {
+            assert_eq!(unit.fmt_quantity(num.unsigned_abs(), num < 0), output);
+        }
+    }
+}
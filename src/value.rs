@@ -0,0 +1,81 @@
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
+
+use crate::word::Word;
+
+/// The maximum number of bits in a big value, so that a typo cannot exhaust the memory.
+#[cfg(feature = "bignum")]
+pub const MAX_BIG_BITS: u64 = 1 << 16;
+
+/// A Value is a number produced by cork: either a number of a fixed-width word,
+/// or, when the width is big, an integer of unbounded size.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Fixed(i128),
+    #[cfg(feature = "bignum")]
+    Big(BigInt),
+}
+
+impl Default for Value {
+    fn default() -> Self {
+        Value::Fixed(0)
+    }
+}
+
+impl Value {
+    /// Converts the value to a number of word, truncating it if it does not fit.
+    pub fn to_fixed(&self, word: Word) -> i128 {
+        match self {
+            Value::Fixed(num) => word.wrap(*num),
+            #[cfg(feature = "bignum")]
+            Value::Big(num) => {
+                // the low 128 bits of the two's complement form
+                let low = u128::try_from(num & BigInt::from(u128::MAX)).unwrap();
+                word.wrap(low as i128)
+            }
+        }
+    }
+
+    /// Converts the value to an unbounded integer, reading a fixed value as a number of word.
+    #[cfg(feature = "bignum")]
+    pub fn to_big(&self, word: Word) -> BigInt {
+        match self {
+            Value::Fixed(num) if word.signed() => BigInt::from(word.wrap(*num)),
+            Value::Fixed(num) => BigInt::from(word.bits_of(*num)),
+            Value::Big(num) => num.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_fixed() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        assert_eq!(Value::Fixed(0xff).to_fixed(i8), -1);
+        assert_eq!(Value::Fixed(0xff).to_fixed(u8), 0xff);
+        assert_eq!(Value::Fixed(0x1ff).to_fixed(u8), 0xff);
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_big_conversion() {
+        let i8 = Word::new(8.try_into().unwrap(), true);
+        let u8 = Word::new(8.try_into().unwrap(), false);
+        let u128 = Word::new(128.try_into().unwrap(), false);
+        let big = |num: &str| Value::Big(num.parse().unwrap());
+
+        assert_eq!(big("-1").to_fixed(u8), 0xff);
+        assert_eq!(big("-129").to_fixed(i8), 127);
+        assert_eq!(
+            big("340282366920938463463374607431768211457").to_fixed(u8),
+            1
+        );
+        assert_eq!(Value::Fixed(-1).to_big(i8), BigInt::from(-1));
+        assert_eq!(Value::Fixed(-1).to_big(u8), BigInt::from(255));
+        assert_eq!(Value::Fixed(-1).to_big(u128), BigInt::from(u128::MAX));
+    }
+}
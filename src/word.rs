@@ -0,0 +1,436 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+/// Width is the number of bits in a word.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "u32")]
+pub struct Width(u32);
+
+impl Width {
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Default for Width {
+    fn default() -> Self {
+        Self(64)
+    }
+}
+
+impl TryFrom<u32> for Width {
+    type Error = String;
+
+    fn try_from(bits: u32) -> Result<Self, Self::Error> {
+        match bits {
+            8 | 16 | 32 | 64 | 128 => Ok(Self(bits)),
+            _ => Err(format!(
+                "{} is not a valid width (expected 8, 16, 32, 64 or 128)",
+                bits
+            )),
+        }
+    }
+}
+
+impl FromStr for Width {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bits = s
+            .parse::<u32>()
+            .map_err(|_| format!("{} is not a valid width", s))?;
+        Self::try_from(bits)
+    }
+}
+
+/// Overflow is the policy followed when the result of an operation does not fit in a word.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+pub enum Overflow {
+    /// Report an error.
+    Checked,
+    /// Discard the bits that do not fit.
+    #[default]
+    Wrapping,
+    /// Clamp the result to the largest or smallest value of the word.
+    Saturating,
+}
+
+impl FromStr for Overflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "checked" => Ok(Self::Checked),
+            "wrapping" => Ok(Self::Wrapping),
+            "saturating" => Ok(Self::Saturating),
+            _ => Err(format!(
+                "{} is not a valid overflow policy (expected checked, wrapping or saturating)",
+                s
+            )),
+        }
+    }
+}
+
+/// Defines an arithmetic operation on words, which follows the overflow policy.
+/// It returns None if the operation overflows under the checked policy.
+macro_rules! arith_op {
+    ($(#[$doc:meta])* $name:ident, $wrapping:ident, $checked:ident, $saturating:ident) => {
+        $(#[$doc])*
+        pub fn $name(&self, lhs: i128, rhs: i128, overflow: Overflow) -> Option<i128> {
+            if self.signed {
+                match overflow {
+                    Overflow::Checked => lhs.$checked(rhs).filter(|num| self.wrap(*num) == *num),
+                    Overflow::Wrapping => Some(self.wrap(lhs.$wrapping(rhs))),
+                    Overflow::Saturating => Some(lhs.$saturating(rhs).clamp(self.min(), self.max())),
+                }
+            } else {
+                let (lhs, rhs) = (lhs as u128, rhs as u128);
+                let max = self.max() as u128;
+                match overflow {
+                    Overflow::Checked => lhs
+                        .$checked(rhs)
+                        .filter(|num| *num <= max)
+                        .map(|num| num as i128),
+                    Overflow::Wrapping => Some(self.wrap(lhs.$wrapping(rhs) as i128)),
+                    Overflow::Saturating => Some(lhs.$saturating(rhs).min(max) as i128),
+                }
+            }
+        }
+    };
+}
+
+/// A Word describes the integer type all evaluation is carried out in.
+/// Every value is truncated to the width of the word, and is sign-extended
+/// if the word is signed.
+///
+/// Values are stored in an i128. For unsigned words, this is the bit-pattern
+/// of the value, so a u128 word reinterprets the sign bit as its top bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Word {
+    width: Width,
+    signed: bool,
+}
+
+impl Default for Word {
+    fn default() -> Self {
+        Self {
+            width: Width::default(),
+            signed: true,
+        }
+    }
+}
+
+impl Word {
+    pub fn new(width: Width, signed: bool) -> Self {
+        Self { width, signed }
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.width.bits()
+    }
+
+    pub fn signed(&self) -> bool {
+        self.signed
+    }
+
+    pub fn set_width(&mut self, width: Width) {
+        self.width = width;
+    }
+
+    pub fn set_signed(&mut self, signed: bool) {
+        self.signed = signed;
+    }
+
+    /// Truncates num to the width of the word, sign-extending it if the word is signed.
+    pub fn wrap(&self, num: i128) -> i128 {
+        let shift = 128 - self.bits();
+        if self.signed {
+            (num << shift) >> shift
+        } else {
+            (((num as u128) << shift) >> shift) as i128
+        }
+    }
+
+    /// The smallest value of the word.
+    pub fn min(&self) -> i128 {
+        if self.signed {
+            i128::MIN >> (128 - self.bits())
+        } else {
+            0
+        }
+    }
+
+    /// The largest value of the word. For a u128 word, this is the bit-pattern of u128::MAX.
+    pub fn max(&self) -> i128 {
+        if self.signed {
+            i128::MAX >> (128 - self.bits())
+        } else {
+            (u128::MAX >> (128 - self.bits())) as i128
+        }
+    }
+
+    /// Compares lhs and rhs, taking into account the signedness of the word.
+    pub fn cmp(&self, lhs: i128, rhs: i128) -> Ordering {
+        if self.signed {
+            lhs.cmp(&rhs)
+        } else {
+            (lhs as u128).cmp(&(rhs as u128))
+        }
+    }
+
+    /// Returns the bits of num in the word, with every bit above the word cleared.
+    pub fn bits_of(&self, num: i128) -> u128 {
+        (num as u128) & (u128::MAX >> (128 - self.bits()))
+    }
+
+    /// Returns the value of the word with the given bits. Bits above the word are ignored.
+    pub fn value_of(&self, bits: u128) -> i128 {
+        self.wrap(bits as i128)
+    }
+
+    /// Formats num as a decimal number, taking into account the signedness of the word.
+    pub fn decimal(&self, num: i128) -> String {
+        if self.signed {
+            num.to_string()
+        } else {
+            (num as u128).to_string()
+        }
+    }
+
+    arith_op!(add, wrapping_add, checked_add, saturating_add);
+    arith_op!(sub, wrapping_sub, checked_sub, saturating_sub);
+    arith_op!(mul, wrapping_mul, checked_mul, saturating_mul);
+    arith_op!(
+        /// Divides lhs by rhs, which must not be 0.
+        div,
+        wrapping_div,
+        checked_div,
+        saturating_div
+    );
+
+    /// Raises base to the power exp, multiplying by squaring.
+    pub fn pow(&self, mut base: i128, mut exp: u128, overflow: Overflow) -> Option<i128> {
+        let mut result = 1;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = self.mul(result, base, overflow)?;
+            }
+            exp >>= 1;
+            // squaring is skipped after the last bit, as it might overflow needlessly
+            if exp > 0 {
+                base = self.mul(base, base, overflow)?;
+            }
+        }
+        Some(result)
+    }
+
+    /// Converts a non-negative num into the word, following the overflow policy
+    /// if it is too large for the word.
+    pub fn fit_unsigned(&self, num: u128, overflow: Overflow) -> Option<i128> {
+        if num <= self.max() as u128 {
+            return Some(num as i128);
+        }
+        match overflow {
+            Overflow::Checked => None,
+            Overflow::Wrapping => Some(self.wrap(num as i128)),
+            Overflow::Saturating => Some(self.max()),
+        }
+    }
+
+    /// Computes the remainder of lhs divided by rhs, which must not be 0.
+    /// The remainder always fits in the word.
+    pub fn rem(&self, lhs: i128, rhs: i128) -> i128 {
+        if self.signed {
+            lhs.wrapping_rem(rhs)
+        } else {
+            ((lhs as u128) % (rhs as u128)) as i128
+        }
+    }
+
+    /// Shifts num left by amount. The shift overflows if it is by the width of the word
+    /// or more, or if any bits of num are shifted out of the word.
+    pub fn shl(&self, num: i128, amount: u32, overflow: Overflow) -> Option<i128> {
+        let in_range = amount < self.bits();
+        let shifted = if in_range {
+            self.wrap(num << amount)
+        } else {
+            0
+        };
+        if in_range && self.shift_right(shifted, amount) == num {
+            return Some(shifted);
+        }
+        match overflow {
+            Overflow::Checked => None,
+            Overflow::Wrapping => Some(shifted),
+            Overflow::Saturating if num == 0 => Some(0),
+            Overflow::Saturating if num < 0 && self.signed => Some(self.min()),
+            Overflow::Saturating => Some(self.max()),
+        }
+    }
+
+    /// Shifts num right by amount. This is an arithmetic shift for signed words
+    /// and a logical shift for unsigned words. The shift overflows if it is by the
+    /// width of the word or more.
+    pub fn shr(&self, num: i128, amount: u32, overflow: Overflow) -> Option<i128> {
+        if amount >= self.bits() && overflow == Overflow::Checked {
+            None
+        } else {
+            Some(self.shift_right(num, amount))
+        }
+    }
+
+    fn shift_right(&self, num: i128, amount: u32) -> i128 {
+        if self.signed {
+            // num is already sign-extended, so shifting out of the word leaves only the sign
+            num >> amount.min(127)
+        } else if amount >= self.bits() {
+            0
+        } else {
+            ((num as u128) >> amount) as i128
+        }
+    }
+}
+
+impl Display for Word {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sign = if self.signed { 'i' } else { 'u' };
+        write!(f, "{}{}", sign, self.bits())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn word(bits: u32, signed: bool) -> Word {
+        Word::new(Width::try_from(bits).unwrap(), signed)
+    }
+
+    #[test]
+    fn test_width_parse() {
+        assert_eq!("32".parse::<Width>().unwrap().bits(), 32);
+        assert!("12".parse::<Width>().is_err());
+        assert!("wide".parse::<Width>().is_err());
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(word(8, false).wrap(0x1ff), 0xff);
+        assert_eq!(word(8, true).wrap(0xff), -1);
+        assert_eq!(word(16, true).wrap(0x8000), -0x8000);
+        assert_eq!(word(32, false).wrap(-1), 0xffff_ffff);
+        assert_eq!(word(128, false).wrap(-1), -1);
+    }
+
+    #[test]
+    fn test_bits_of() {
+        assert_eq!(word(8, true).bits_of(-1), 0xff);
+        assert_eq!(word(16, false).bits_of(0x8001), 0x8001);
+        assert_eq!(word(128, true).bits_of(-1), u128::MAX);
+        assert_eq!(word(8, true).value_of(0x1ff), -1);
+        assert_eq!(word(8, false).value_of(0x1ff), 0xff);
+    }
+
+    #[test]
+    fn test_cmp() {
+        assert_eq!(word(8, true).cmp(-1, 1), Ordering::Less);
+        assert_eq!(word(8, false).cmp(0xff, 1), Ordering::Greater);
+        assert_eq!(word(128, false).cmp(-1, 1), Ordering::Greater);
+        assert_eq!(word(128, true).cmp(-1, -1), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_overflow_parse() {
+        assert_eq!("checked".parse::<Overflow>().unwrap(), Overflow::Checked);
+        assert_eq!("wrapping".parse::<Overflow>().unwrap(), Overflow::Wrapping);
+        assert_eq!(
+            "saturating".parse::<Overflow>().unwrap(),
+            Overflow::Saturating
+        );
+        assert!("clamp".parse::<Overflow>().is_err());
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(word(8, true).min(), -0x80);
+        assert_eq!(word(8, true).max(), 0x7f);
+        assert_eq!(word(8, false).min(), 0);
+        assert_eq!(word(8, false).max(), 0xff);
+        assert_eq!(word(128, true).min(), i128::MIN);
+        assert_eq!(word(128, false).max() as u128, u128::MAX);
+    }
+
+    #[test]
+    fn test_arith_overflow() {
+        use Overflow::*;
+
+        let i8 = word(8, true);
+        assert_eq!(i8.add(0x7f, 1, Checked), None);
+        assert_eq!(i8.add(0x7f, 1, Wrapping), Some(-0x80));
+        assert_eq!(i8.add(0x7f, 1, Saturating), Some(0x7f));
+        assert_eq!(i8.sub(-0x80, 1, Saturating), Some(-0x80));
+        assert_eq!(i8.mul(-0x40, 2, Checked), Some(-0x80));
+        assert_eq!(i8.div(-0x80, -1, Checked), None);
+        assert_eq!(i8.div(-0x80, -1, Wrapping), Some(-0x80));
+        assert_eq!(i8.div(-0x80, -1, Saturating), Some(0x7f));
+
+        let u8 = word(8, false);
+        assert_eq!(u8.sub(0, 1, Checked), None);
+        assert_eq!(u8.sub(0, 1, Wrapping), Some(0xff));
+        assert_eq!(u8.sub(0, 1, Saturating), Some(0));
+        assert_eq!(u8.mul(0x10, 0x10, Saturating), Some(0xff));
+
+        let u128 = word(128, false);
+        assert_eq!(u128.add(-1, 1, Checked), None);
+        assert_eq!(u128.add(-1, 1, Saturating), Some(-1));
+        assert_eq!(u128.mul(1 << 64, 1 << 63, Checked), Some(i128::MIN));
+    }
+
+    #[test]
+    fn test_pow() {
+        use Overflow::*;
+
+        assert_eq!(word(64, true).pow(3, 4, Checked), Some(81));
+        assert_eq!(word(8, true).pow(-2, 7, Checked), Some(-128));
+        assert_eq!(word(8, true).pow(2, 7, Checked), None);
+        assert_eq!(word(8, true).pow(2, 7, Saturating), Some(127));
+        assert_eq!(word(8, false).pow(2, 9, Wrapping), Some(0));
+        assert_eq!(word(8, false).pow(1, u128::MAX, Checked), Some(1));
+    }
+
+    #[test]
+    fn test_fit_unsigned() {
+        use Overflow::*;
+
+        assert_eq!(word(8, true).fit_unsigned(127, Checked), Some(127));
+        assert_eq!(word(8, true).fit_unsigned(128, Checked), None);
+        assert_eq!(word(8, true).fit_unsigned(128, Wrapping), Some(-128));
+        assert_eq!(word(8, false).fit_unsigned(256, Saturating), Some(255));
+        assert_eq!(word(128, false).fit_unsigned(u128::MAX, Checked), Some(-1));
+    }
+
+    #[test]
+    fn test_shift() {
+        use Overflow::*;
+
+        assert_eq!(word(8, false).shl(0x81, 1, Wrapping), Some(0x02));
+        assert_eq!(word(8, false).shl(0x81, 1, Checked), None);
+        assert_eq!(word(8, false).shl(0x81, 1, Saturating), Some(0xff));
+        assert_eq!(word(8, false).shl(1, 8, Wrapping), Some(0));
+        assert_eq!(word(8, false).shl(1, 8, Checked), None);
+        assert_eq!(word(8, true).shl(1, 7, Wrapping), Some(-0x80));
+        assert_eq!(word(8, true).shl(1, 7, Checked), None);
+        assert_eq!(word(8, true).shl(-1, 7, Checked), Some(-0x80));
+        assert_eq!(word(8, true).shl(-3, 7, Saturating), Some(-0x80));
+        assert_eq!(word(8, true).shr(-0x80, 7, Checked), Some(-1));
+        assert_eq!(word(8, true).shr(-0x80, 200, Wrapping), Some(-1));
+        assert_eq!(word(8, true).shr(-0x80, 8, Checked), None);
+        assert_eq!(word(8, false).shr(0x80, 7, Wrapping), Some(1));
+        assert_eq!(word(8, false).shr(0x80, 8, Saturating), Some(0));
+    }
+}